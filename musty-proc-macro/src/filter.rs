@@ -0,0 +1,180 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::{
+    parenthesized,
+    parse::{Parse, ParseStream},
+    Ident, Token, Type,
+};
+
+/// The input of the `filter!()` macro: `filter!(Model, <expression>)`
+pub(crate) struct FilterInput {
+    model: Type,
+    expr: FilterExpr,
+}
+
+/// A filter expression, parsed with the usual precedence: `!` binds tighter than `&&`, which binds tighter than `||`
+enum FilterExpr {
+    Compare(Ident, CompareOp, TokenStream),
+    Exists(Ident),
+    And(Vec<FilterExpr>),
+    Or(Vec<FilterExpr>),
+    Not(Box<FilterExpr>),
+}
+
+enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Lt,
+    In,
+}
+
+impl Parse for FilterInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let model = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let expr = parse_or(input)?;
+        input.parse::<Option<Token![,]>>()?;
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens after filter expression"));
+        }
+        Ok(Self { model, expr })
+    }
+}
+
+fn parse_or(input: ParseStream) -> syn::Result<FilterExpr> {
+    let mut exprs = vec![parse_and(input)?];
+    while input.peek(Token![||]) {
+        input.parse::<Token![||]>()?;
+        exprs.push(parse_and(input)?);
+    }
+    Ok(match exprs.len() {
+        1 => exprs.remove(0),
+        _ => FilterExpr::Or(exprs),
+    })
+}
+
+fn parse_and(input: ParseStream) -> syn::Result<FilterExpr> {
+    let mut exprs = vec![parse_unary(input)?];
+    while input.peek(Token![&&]) {
+        input.parse::<Token![&&]>()?;
+        exprs.push(parse_unary(input)?);
+    }
+    Ok(match exprs.len() {
+        1 => exprs.remove(0),
+        _ => FilterExpr::And(exprs),
+    })
+}
+
+fn parse_unary(input: ParseStream) -> syn::Result<FilterExpr> {
+    if input.peek(Token![!]) && !input.peek(Token![!=]) {
+        input.parse::<Token![!]>()?;
+        return Ok(FilterExpr::Not(Box::new(parse_unary(input)?)));
+    }
+
+    if input.peek(syn::token::Paren) {
+        let content;
+        parenthesized!(content in input);
+        return parse_or(&content);
+    }
+
+    let field: Ident = input.parse()?;
+
+    if field == "exists" && input.peek(syn::token::Paren) {
+        let content;
+        parenthesized!(content in input);
+        let field: Ident = content.parse()?;
+        if !content.is_empty() {
+            return Err(content.error("expected a single field name"));
+        }
+        return Ok(FilterExpr::Exists(field));
+    }
+
+    // `>=` and `<=` start with `>` and `<`, so they are rejected before those are parsed
+    if input.peek(Token![>=]) || input.peek(Token![<=]) {
+        let (op, strict) = match input.peek(Token![>=]) {
+            true => (">=", ">"),
+            false => ("<=", "<"),
+        };
+        return Err(input.error(format!(
+            "`{}` is not supported, use `{} {} value || {} == value` instead",
+            op, field, strict, field
+        )));
+    }
+
+    let op = if input.peek(Token![==]) {
+        input.parse::<Token![==]>()?;
+        CompareOp::Eq
+    } else if input.peek(Token![!=]) {
+        input.parse::<Token![!=]>()?;
+        CompareOp::Ne
+    } else if input.peek(Token![>]) {
+        input.parse::<Token![>]>()?;
+        CompareOp::Gt
+    } else if input.peek(Token![<]) {
+        input.parse::<Token![<]>()?;
+        CompareOp::Lt
+    } else if input.peek(Token![in]) {
+        input.parse::<Token![in]>()?;
+        CompareOp::In
+    } else {
+        return Err(input.error("expected one of `==`, `!=`, `>`, `<` or `in`"));
+    };
+
+    // the value is every token up to the next `&&`, `||` or `,`, so values containing those need to be parenthesized
+    let mut value = TokenStream::new();
+    while !input.is_empty()
+        && !input.peek(Token![&&])
+        && !input.peek(Token![||])
+        && !input.peek(Token![,])
+    {
+        value.extend(std::iter::once(input.parse::<TokenTree>()?));
+    }
+    if value.is_empty() {
+        return Err(input.error("expected a value"));
+    }
+
+    Ok(FilterExpr::Compare(field, op, value))
+}
+
+impl FilterExpr {
+    fn expand(&self, model: &Type) -> TokenStream {
+        let path = |field: &Ident| quote! { <#model>::fields().#field };
+
+        match self {
+            Self::Compare(field, op, value) => {
+                let path = path(field);
+                let constructor = match op {
+                    CompareOp::Eq => quote! { eq },
+                    CompareOp::Ne => quote! { ne },
+                    CompareOp::Gt => quote! { gt },
+                    CompareOp::Lt => quote! { lt },
+                    CompareOp::In => quote! { in_ },
+                };
+                quote! { #path.#constructor(#value) }
+            }
+            Self::Exists(field) => {
+                let path = path(field);
+                quote! { #path.exists() }
+            }
+            Self::And(exprs) => {
+                let exprs = exprs.iter().map(|expr| expr.expand(model));
+                quote! { musty::filter::Filter::And(vec![#(#exprs),*]) }
+            }
+            Self::Or(exprs) => {
+                let exprs = exprs.iter().map(|expr| expr.expand(model));
+                quote! { musty::filter::Filter::Or(vec![#(#exprs),*]) }
+            }
+            Self::Not(expr) => {
+                let expr = expr.expand(model);
+                quote! { musty::filter::Filter::Not(Box::new(#expr)) }
+            }
+        }
+    }
+}
+
+impl ToTokens for FilterInput {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        tokens.extend(self.expr.expand(&self.model));
+    }
+}
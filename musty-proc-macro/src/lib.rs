@@ -1,9 +1,12 @@
 use darling::{FromDeriveInput, FromMeta};
+use filter::FilterInput;
 use model::meta_model::{MetaModelAttr, MetaModelDerive};
 use proc_macro::{self, TokenStream};
 use proc_macro_error::proc_macro_error;
+use quote::ToTokens;
 use syn::{parse_macro_input, AttributeArgs, DeriveInput};
 
+mod filter;
 mod model;
 mod util;
 
@@ -22,6 +25,63 @@ mod util;
 /// 
 /// this derives `serde::Serialize`, `serde::Deserialize`, `Debug`, and adds the necessary serde attributes to the struct and id field.
 /// the id field is also changed to be of type `musty::prelude::Id<Self, I>`, where `I` is the type of your `id` field (in this case: `ObjectId`)
+///
+/// The id can be generated client-side when a model without an id is saved, with `#[musty(id(generate = "..."))]`:
+/// `uuid_v4` or `uuid_v7` for a `musty::generate::Uuid` id, `ulid` for a `String` id, and `snowflake` or `sequence` for an `i64` id
+/// (see `musty::generate`).
+///
+/// Everything else on the struct is kept: its own derives (the required ones are only added if missing), doc comments,
+/// serde attributes (field names follow `#[serde(rename)]` and `#[serde(rename_all)]`) and generic parameters.
+/// Generic parameters must be bounded in the where clause by what a `Model` needs:
+/// ```ignore
+/// #[model(mongo(collection = "events"))]
+/// #[derive(Clone, PartialEq)]
+/// #[serde(rename_all = "camelCase")]
+/// struct Event<P>
+/// where
+///     P: Serialize + DeserializeOwned + Send + Sync + Unpin + 'static,
+/// {
+///     id: ObjectId,
+///     event_name: String,
+///     payload: P,
+/// }
+/// ```
+/// generic MongoDB models are not registered for `Musty::sync_all_indexes`, sync their indexes with `Musty::sync_indexes`.
+///
+/// On an enum, every variant is stored in the same collection, with a discriminator field (`_type`, or `#[model(discriminator = "kind")]`)
+/// set to the name of the variant (or `#[musty(rename = "...")]` on the variant).
+/// Every variant also gets its own model struct (`LoginEvent` for `Event::Login`, or `#[musty(model = "...")]` on the variant),
+/// whose queries only find models of that variant:
+/// ```ignore
+/// #[model(mongo(collection = "events"))]
+/// enum Event {
+///     Login { id: ObjectId, user: String },
+///     Purchase { id: ObjectId, user: String, amount: u32 },
+/// }
+///
+/// let logins = LoginEvent::find_many(&db, filter!(LoginEvent, user == "jonah"), None).await?;
+/// ```
+///
+/// With `#[model(graphql)]` (and the `graphql` feature of musty), a model struct derives `async_graphql::SimpleObject`,
+/// and gets a `{Model}Input` struct deriving `async_graphql::InputObject` for create and update mutations,
+/// with every field except the id, the fields skipped in GraphQL, and the fields set by musty (versions, timestamps, soft delete markers).
+/// The model converts from its input (without an id, so it is inserted when saved), and `input.update(&mut model)` sets its fields.
+/// The crate using the macro must depend on `async-graphql`:
+/// ```ignore
+/// #[model(mongo(collection = "users"), graphql(name = "Person", input = "NewPerson", complex))]
+/// struct User {
+///     id: ObjectId,
+///     #[musty(graphql(name = "displayName"))]
+///     name: String,
+///     #[musty(graphql(skip))]
+///     password_hash: String,
+/// }
+///
+/// let mut user = User::from(input);
+/// user.save(&db).await?;
+/// ```
+/// `complex` resolves more fields with an `#[async_graphql::ComplexObject]` impl block on the model.
+/// GraphQL is not supported on generic and enum models, derive their GraphQL types instead.
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn model(args: TokenStream, stream: TokenStream) -> TokenStream {
@@ -38,3 +98,24 @@ pub fn model(args: TokenStream, stream: TokenStream) -> TokenStream {
 
     meta_model.expand(arg_model)
 }
+
+/// Builds a database-agnostic `musty::filter::Filter` for a model, checking the field names against the model at compile time.
+///
+/// Usage:
+/// ```ignore
+/// use musty::prelude::*;
+/// let filter = filter!(User, name == "jonah" && (age > 18 || !exists(email)));
+/// let user = User::find_one(&db, filter, None).await?;
+/// ```
+///
+/// Supported operators are `==`, `!=`, `>`, `<`, `in` (any of an iterator of values), and `exists(field)`,
+/// which can be combined with `&&`, `||`, `!` and parentheses (`>=` and `<=` are not supported, write `age > 18 || age == 18` instead).
+/// Values are any expression up to the next `&&` or `||`, wrap them in parentheses if they contain either.
+/// Fields are referred to by their name on the struct, and are translated to the name they are stored with (ex: `#[musty(rename)]`, or `_id`).
+/// Each comparison expands to the model's typed field path (ex: `User::fields().age.gt(18)`), so values are checked against the field's type.
+#[proc_macro]
+pub fn filter(input: TokenStream) -> TokenStream {
+    parse_macro_input!(input as FilterInput)
+        .into_token_stream()
+        .into()
+}
@@ -0,0 +1,342 @@
+use darling::{ast::Style, FromVariant};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::{parse_quote, Attribute, Ident};
+
+use super::meta_model::{
+    apply_rename_all, expand_derives, expand_field_paths, expand_generate_id, expand_id_attr,
+    expand_serde_bound, expand_struct_fields, expand_where_sized, get_id_field, serde_attr,
+    MetaModelAttr, MetaModelDerive, MetaModelField,
+};
+
+/// A variant of a model enum:
+/// #[musty(rename = "login", model = "LoginEvent")]
+#[derive(FromVariant)]
+#[darling(attributes(musty), forward_attrs)]
+pub(crate) struct MetaModelVariant {
+    pub(crate) ident: Ident,
+    /// the other attributes of the variant (doc comments, serde attributes, ...), kept on the expanded variant
+    pub(crate) attrs: Vec<Attribute>,
+    pub(crate) fields: darling::ast::Fields<MetaModelField>,
+    /// the value of the discriminator for this variant: #[musty(rename = "login")],
+    /// defaults to the serde name of the variant (from `#[serde(rename)]` on the variant or `#[serde(rename_all)]` on the enum)
+    #[darling(default)]
+    pub(crate) rename: Option<String>,
+    /// the name of the model struct for this variant: #[musty(model = "LoginEvent")],
+    /// defaults to the name of the variant followed by the name of the enum (ex: `Event::Login` -> `LoginEvent`)
+    #[darling(default)]
+    pub(crate) model: Option<Ident>,
+}
+
+/// The enum model a model struct is a variant of
+pub(crate) struct VariantOf {
+    /// the enum model
+    pub(crate) model: Ident,
+    /// the variant of the enum model
+    pub(crate) variant: Ident,
+    /// the name of the discriminator field
+    pub(crate) field: String,
+    /// the value of the discriminator for the variant
+    pub(crate) value: String,
+}
+
+/// Expands a model enum: several variants stored in one collection, told apart by a discriminator field (`_type` by default).
+/// The enum is internally tagged with the discriminator, and implements `Model` (and `MongoModel`) for the whole collection.
+/// Every variant also gets its own model struct with the same fields, whose queries only find models of that variant,
+/// and which converts from and into the enum. The model structs have the generics and derives of the enum
+pub(crate) fn expand_enum_model(
+    meta: MetaModelDerive,
+    args: MetaModelAttr,
+) -> proc_macro2::TokenStream {
+    let ident = &meta.ident;
+    let vis = &meta.vis;
+    let generics = meta.generics.clone();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let where_sized = expand_where_sized(&generics);
+    let attrs = &meta.attrs;
+    let derives = expand_derives(attrs);
+    let serde_bound = expand_serde_bound(&generics, attrs);
+    let rule = serde_attr(attrs, "rename_all");
+    let is_mongo = args.mongo.is_some();
+    let discriminator = args
+        .discriminator
+        .clone()
+        .unwrap_or_else(|| String::from("_type"));
+
+    if args.soft_delete || args.hooks || args.graphql.is_some() {
+        abort!(
+            ident.span(),
+            "`#[model(soft_delete)]`, `#[model(hooks)]` and `#[model(graphql)]` are not supported on enum models"
+        );
+    }
+
+    let variants = match &meta.data {
+        darling::ast::Data::Enum(variants) => variants,
+        _ => abort!(ident.span(), "Model must be an enum"),
+    };
+    if variants.is_empty() {
+        abort!(ident.span(), "{} must have at least one variant", ident);
+    }
+
+    let mut id_type = None;
+    for variant in variants.iter() {
+        if variant.fields.style != Style::Struct {
+            abort!(
+                variant.ident.span(),
+                "The variants of an enum model must have named fields"
+            );
+        }
+
+        for field in variant.fields.iter() {
+            let field_ident = field.ident.as_ref().unwrap();
+            if field.version
+                || field.created_at
+                || field.updated_at
+                || field.deleted_at
+                || field.validate.is_some()
+            {
+                abort!(
+                    field_ident.span(),
+                    "Versions, timestamps, soft delete markers and validations are not supported on enum models"
+                );
+            }
+            if field.stored_name(is_mongo).as_deref() == Some(discriminator.as_str()) {
+                abort!(
+                    field_ident.span(),
+                    "`{}` is stored as `{}`, which is the discriminator of {}",
+                    field_ident,
+                    discriminator,
+                    ident
+                );
+            }
+        }
+
+        let (variant_id_field, variant_id_type) =
+            get_id_field(&variant.ident, variant.fields.iter());
+        let strategy =
+            |field: &MetaModelField| field.id.as_ref().and_then(|id| id.generate.clone());
+        let (first_id_field, _) = get_id_field(&variants[0].ident, variants[0].fields.iter());
+        if strategy(variant_id_field) != strategy(first_id_field) {
+            abort!(
+                variant.ident.span(),
+                "The `id` fields of every variant of {} must have the same id strategy",
+                ident
+            );
+        }
+        match &id_type {
+            None => id_type = Some(variant_id_type),
+            Some(id_type)
+                if quote!(#id_type).to_string() != quote!(#variant_id_type).to_string() =>
+            {
+                abort!(
+                    variant.ident.span(),
+                    "The `id` fields of every variant of {} must have the same type",
+                    ident
+                )
+            }
+            Some(_) => {}
+        }
+    }
+    let id_type = id_type.unwrap();
+    let generate_id = expand_generate_id(get_id_field(ident, variants[0].fields.iter()).0);
+
+    let value = |variant: &MetaModelVariant| {
+        variant
+            .rename
+            .clone()
+            .or_else(|| serde_attr(&variant.attrs, "rename"))
+            .or_else(|| {
+                rule.as_deref()
+                    .map(|rule| apply_rename_all(&variant.ident, rule))
+            })
+            .unwrap_or_else(|| variant.ident.to_string())
+    };
+    let model_ident = |variant: &MetaModelVariant| {
+        variant
+            .model
+            .clone()
+            .unwrap_or_else(|| format_ident!("{}{}", variant.ident, ident))
+    };
+
+    let id_attr = expand_id_attr(&args);
+    let enum_variants = variants
+        .iter()
+        .map(|variant| {
+            let variant_ident = &variant.ident;
+            let variant_attrs = &variant.attrs;
+            // a variant renamed with serde already has its discriminator value
+            let rename = serde_attr(&variant.attrs, "rename").is_none().then(|| {
+                let value = value(variant);
+                quote! { #[serde(rename = #value)] }
+            });
+            let (id_field, _) = get_id_field(variant_ident, variant.fields.iter());
+            let id_attrs = &id_field.attrs;
+            let fields = expand_struct_fields(variant.fields.iter(), false, false);
+            quote! {
+                #(#variant_attrs)*
+                #rename
+                #variant_ident {
+                    #(#id_attrs)*
+                    #id_attr
+                    id: musty::prelude::Id<Self, #id_type>,
+                    #(#fields),*
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let variant_idents = variants
+        .iter()
+        .map(|variant| variant.ident.clone())
+        .collect::<Vec<_>>();
+
+    // the typed field paths of the enum are the fields shared by every variant, stored with the same name and type
+    let (first, others) = variants.split_first().unwrap();
+    let common_fields = first
+        .fields
+        .iter()
+        .filter_map(|field| {
+            let field_ident = field.ident.as_ref().unwrap();
+            let path = field.stored_name(is_mongo)?;
+            let ty = &field.ty;
+            let ty = quote!(#ty).to_string();
+            let shared = others.iter().all(|variant| {
+                variant.fields.iter().any(|other| {
+                    let other_ty = &other.ty;
+                    other.ident == field.ident
+                        && other.stored_name(is_mongo).as_ref() == Some(&path)
+                        && (field.is_id() || quote!(#other_ty).to_string() == ty)
+                })
+            });
+            if !shared {
+                return None;
+            }
+            let ty = if field.is_id() {
+                quote! { musty::prelude::Id<#ident #ty_generics, #id_type> }
+            } else {
+                let ty = &field.ty;
+                quote! { #ty }
+            };
+            Some((field_ident, vis, path, ty))
+        })
+        .collect::<Vec<_>>();
+    let fields = expand_field_paths(ident, vis, &generics, &common_fields);
+
+    let mongo_model = args
+        .mongo
+        .as_ref()
+        .map(|mongo| super::mongo_model::expand_mongo_model(&meta, mongo, None));
+
+    let mut variant_models = Vec::new();
+    let variants = match meta.data {
+        darling::ast::Data::Enum(variants) => variants,
+        _ => unreachable!(),
+    };
+    for variant in variants {
+        let variant_ident = variant.ident.clone();
+        let model = model_ident(&variant);
+        // the model struct of a variant has the derives of the enum, and stores its fields like the variant does
+        let mut model_attrs = attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("derive"))
+            .cloned()
+            .collect::<Vec<Attribute>>();
+        if let Some(rule) = serde_attr(&variant.attrs, "rename_all") {
+            model_attrs.push(parse_quote! { #[serde(rename_all = #rule)] });
+        }
+        let variant_of = VariantOf {
+            model: ident.clone(),
+            variant: variant.ident.clone(),
+            field: discriminator.clone(),
+            value: value(&variant),
+        };
+
+        let field_idents = variant
+            .fields
+            .iter()
+            .filter(|field| !field.is_id())
+            .map(|field| field.ident.clone().unwrap())
+            .collect::<Vec<_>>();
+
+        // the fields of the variant model are as visible as the fields of the enum
+        let mut fields = variant.fields;
+        for field in fields.fields.iter_mut() {
+            field.vis = vis.clone();
+        }
+        let variant_model = MetaModelDerive {
+            ident: model.clone(),
+            vis: vis.clone(),
+            generics: generics.clone(),
+            attrs: model_attrs,
+            data: darling::ast::Data::Struct(fields),
+        }
+        .expand_model(&args, Some(&variant_of));
+
+        variant_models.push(quote! {
+            #variant_model
+
+            #[automatically_derived]
+            impl #impl_generics From<#model #ty_generics> for #ident #ty_generics #where_clause {
+                fn from(model: #model #ty_generics) -> Self {
+                    Self::#variant_ident {
+                        id: model.id.cast(),
+                        #(#field_idents: model.#field_idents),*
+                    }
+                }
+            }
+
+            /// Fails with the model if it is another variant
+            #[automatically_derived]
+            impl #impl_generics TryFrom<#ident #ty_generics> for #model #ty_generics #where_clause {
+                type Error = #ident #ty_generics;
+
+                fn try_from(model: #ident #ty_generics) -> std::result::Result<Self, Self::Error> {
+                    match model {
+                        #ident::#variant_ident { id, #(#field_idents),* } => Ok(Self {
+                            id: id.cast(),
+                            #(#field_idents),*
+                        }),
+                        #[allow(unreachable_patterns)]
+                        model => Err(model),
+                    }
+                }
+            }
+        });
+    }
+
+    quote! {
+        #derives
+        #(#attrs)*
+        #serde_bound
+        #[serde(tag = #discriminator)]
+        #vis enum #ident #generics #where_clause {
+            #(#enum_variants),*
+        }
+
+        #fields
+
+        #[musty::prelude::async_trait]
+        #[automatically_derived]
+        impl #impl_generics musty::prelude::Model for #ident #ty_generics #where_sized {
+            type Id = #id_type;
+
+            fn id(&self) -> &Id<Self, #id_type> {
+                match self {
+                    #(Self::#variant_idents { id, .. } => id),*
+                }
+            }
+
+            fn set_id(&mut self, id: Id<Self, #id_type>) {
+                match self {
+                    #(Self::#variant_idents { id: current, .. } => *current = id),*
+                }
+            }
+
+            #generate_id
+        }
+
+        #mongo_model
+
+        #(#variant_models)*
+    }
+}
@@ -0,0 +1,224 @@
+use darling::FromMeta;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::{Ident, NestedMeta};
+
+use super::meta_model::{MetaModelDerive, MetaModelField};
+
+/// GraphQL attributes for a model struct:
+/// #[model(graphql)] or #[model(graphql(name = "Person", input = "NewPerson", complex))]
+#[derive(Default)]
+pub(crate) struct ModelGraphqlAttrs {
+    /// the name of the GraphQL object, defaults to the name of the model
+    pub(crate) name: Option<String>,
+    /// the name of the input object struct (and GraphQL input object), defaults to the name of the model followed by `Input`
+    pub(crate) input: Option<Ident>,
+    /// resolve more fields with an `#[async_graphql::ComplexObject]` impl block on the model
+    pub(crate) complex: bool,
+}
+
+impl FromMeta for ModelGraphqlAttrs {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        #[derive(FromMeta)]
+        struct GraphqlArgs {
+            #[darling(default)]
+            name: Option<String>,
+            #[darling(default)]
+            input: Option<Ident>,
+            #[darling(default)]
+            complex: bool,
+        }
+
+        let args = GraphqlArgs::from_list(items)?;
+        Ok(Self {
+            name: args.name,
+            input: args.input,
+            complex: args.complex,
+        })
+    }
+}
+
+/// GraphQL attributes for a field:
+/// #[musty(graphql(skip))] or #[musty(graphql(name = "displayName"))]
+#[derive(Default, FromMeta)]
+#[darling(default)]
+pub(crate) struct MustyGraphqlFieldAttrs {
+    /// hide the field from the GraphQL object and the input object
+    pub(crate) skip: bool,
+    /// the name of the field in GraphQL, defaults to the camelCase name of the field
+    pub(crate) name: Option<String>,
+}
+
+/// Expands the attributes deriving `async_graphql::SimpleObject` for a model with #[model(graphql)]
+pub(crate) fn expand_graphql_object_attrs(
+    meta: &MetaModelDerive,
+    graphql: &ModelGraphqlAttrs,
+) -> proc_macro2::TokenStream {
+    if !meta.generics.params.is_empty() {
+        abort!(
+            meta.ident.span(),
+            "`#[model(graphql)]` is not supported on generic models, derive the GraphQL types of the model instead"
+        );
+    }
+
+    let name = graphql
+        .name
+        .as_ref()
+        .map(|name| quote! { #[graphql(name = #name)] });
+    let complex = graphql.complex.then(|| quote! { #[graphql(complex)] });
+    quote! {
+        #[derive(async_graphql::SimpleObject)]
+        #name
+        #complex
+    }
+}
+
+/// Expands the `#[graphql(...)]` attributes of a field of a model with #[model(graphql)]
+pub(crate) fn expand_graphql_field_attrs(field: &MetaModelField) -> proc_macro2::TokenStream {
+    let graphql = match field.graphql.as_ref() {
+        Some(graphql) => graphql,
+        None => return quote! {},
+    };
+
+    let skip = graphql.skip.then(|| quote! { #[graphql(skip)] });
+    let name = graphql
+        .name
+        .as_ref()
+        .map(|name| quote! { #[graphql(name = #name)] });
+    quote! {
+        #skip
+        #name
+    }
+}
+
+/// Expands the input object of a model with #[model(graphql)], for create and update mutations:
+/// a `{Model}Input` struct deriving `async_graphql::InputObject`, with the fields of the model which are set by clients.
+/// The id, the fields skipped in GraphQL, and the fields managed by musty (version, timestamps and soft delete marker) are left out
+///
+/// The model converts from its input into a model without an id (which is inserted when saved), with the default value of the fields managed by musty.
+/// The fields skipped in GraphQL can not be defaulted, so if there are any, the input has an `into_model` function taking them instead of the conversion.
+/// `update` sets the fields of an existing model from an input
+pub(crate) fn expand_graphql_input(
+    meta: &MetaModelDerive,
+    graphql: &ModelGraphqlAttrs,
+) -> proc_macro2::TokenStream {
+    let ident = &meta.ident;
+    let vis = &meta.vis;
+    let input_ident = graphql
+        .input
+        .clone()
+        .unwrap_or_else(|| format_ident!("{}Input", ident));
+
+    let fields = match &meta.data {
+        darling::ast::Data::Struct(fields) => fields,
+        _ => abort!(ident.span(), "Model must be a struct"),
+    };
+
+    // fields set by musty itself, and fields hidden from GraphQL, are not part of the input
+    let managed = |field: &MetaModelField| {
+        field.version || field.created_at || field.updated_at || field.deleted_at
+    };
+    let hidden =
+        |field: &MetaModelField| field.graphql.as_ref().is_some_and(|graphql| graphql.skip);
+    let fields = fields.iter().filter(|field| !field.is_id());
+    let managed_fields = fields
+        .clone()
+        .filter(|field| managed(field))
+        .collect::<Vec<_>>();
+    let hidden_fields = fields
+        .clone()
+        .filter(|field| !managed(field) && hidden(field))
+        .collect::<Vec<_>>();
+    let input_fields = fields
+        .filter(|field| !managed(field) && !hidden(field))
+        .collect::<Vec<_>>();
+
+    let declarations = input_fields.iter().map(|field| {
+        let field_ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let docs = field.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+        let graphql_attrs = expand_graphql_field_attrs(field);
+        quote! {
+            #(#docs)*
+            #graphql_attrs
+            #vis #field_ident: #ty
+        }
+    });
+    let input_idents = input_fields
+        .iter()
+        .map(|field| field.ident.as_ref().unwrap())
+        .collect::<Vec<_>>();
+    let managed_idents = managed_fields
+        .iter()
+        .map(|field| field.ident.as_ref().unwrap())
+        .collect::<Vec<_>>();
+    let hidden_idents = hidden_fields
+        .iter()
+        .map(|field| field.ident.as_ref().unwrap())
+        .collect::<Vec<_>>();
+    let hidden_types = hidden_fields.iter().map(|field| &field.ty);
+
+    // the input object is named after the struct, or after the GraphQL name of the model
+    let name = match (&graphql.input, &graphql.name) {
+        (None, Some(name)) => {
+            let name = format!("{}Input", name);
+            Some(quote! { #[graphql(name = #name)] })
+        }
+        _ => None,
+    };
+    let doc = format!(
+        "The fields of [`{}`] set by create and update mutations.",
+        ident
+    );
+
+    let into_model = match hidden_fields.is_empty() {
+        true => quote! {
+            #[automatically_derived]
+            impl From<#input_ident> for #ident {
+                /// A new model without an id, so it is inserted when saved
+                fn from(input: #input_ident) -> Self {
+                    Self {
+                        id: Default::default(),
+                        #(#input_idents: input.#input_idents,)*
+                        #(#managed_idents: Default::default(),)*
+                    }
+                }
+            }
+        },
+        false => quote! {
+            impl #input_ident {
+                /// A new model without an id (so it is inserted when saved), with the fields of this input and the fields hidden from GraphQL
+                #vis fn into_model(self, #(#hidden_idents: #hidden_types),*) -> #ident {
+                    #ident {
+                        id: Default::default(),
+                        #(#input_idents: self.#input_idents,)*
+                        #(#hidden_idents,)*
+                        #(#managed_idents: Default::default(),)*
+                    }
+                }
+            }
+        },
+    };
+
+    quote! {
+        #[doc = #doc]
+        #[derive(async_graphql::InputObject)]
+        #name
+        #vis struct #input_ident {
+            #(#declarations),*
+        }
+
+        #into_model
+
+        impl #input_ident {
+            /// Sets the fields of an existing model to the fields of this input
+            #vis fn update(self, model: &mut #ident) {
+                #(model.#input_idents = self.#input_idents;)*
+            }
+        }
+    }
+}
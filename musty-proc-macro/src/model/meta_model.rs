@@ -2,10 +2,20 @@ use darling::{FromDeriveInput, FromField, FromMeta};
 
 use proc_macro2::Span;
 use proc_macro_error::abort;
-use quote::quote;
-use syn::{Ident, Path, Type, TypePath, Visibility};
+use quote::{format_ident, quote};
+use syn::{
+    punctuated::Punctuated, Attribute, Generics, Ident, Lit, Meta, MetaNameValue, NestedMeta, Path,
+    Token, Type, TypePath, Visibility,
+};
 
+use super::enum_model::{MetaModelVariant, VariantOf};
+use super::graphql_model::{
+    expand_graphql_field_attrs, expand_graphql_input, expand_graphql_object_attrs,
+    ModelGraphqlAttrs, MustyGraphqlFieldAttrs,
+};
 use super::mongo_model::{ModelMongoAttrs, MustyMongoFieldAttrs};
+use super::validate::FieldValidation;
+use crate::util::string::rename_all;
 
 /// Attributes for a model struct:
 /// #[model(mongo(...))]
@@ -13,36 +23,403 @@ use super::mongo_model::{ModelMongoAttrs, MustyMongoFieldAttrs};
 #[darling(default)]
 pub(crate) struct MetaModelAttr {
     pub(crate) mongo: Option<ModelMongoAttrs>,
+    /// mark models as deleted instead of deleting them: #[model(soft_delete)]
+    /// uses the `deleted_at` field (or field with attribute #[musty(deleted_at)]) as the marker
+    pub(crate) soft_delete: bool,
+    /// run the lifecycle hooks of the `ModelHooks` implementation of the model: #[model(hooks)]
+    pub(crate) hooks: bool,
+    /// the field storing the variant of an enum model: #[model(discriminator = "kind")], defaults to `_type`
+    pub(crate) discriminator: Option<String>,
+    /// derive the GraphQL object and input object of the model: #[model(graphql)], see `graphql_model`
+    pub(crate) graphql: Option<ModelGraphqlAttrs>,
 }
 
 /// A field on a model struct
 #[derive(FromField)]
-#[darling(attributes(musty))]
+#[darling(attributes(musty), forward_attrs)]
 pub(crate) struct MetaModelField {
     pub(crate) ident: Option<Ident>,
     pub(crate) vis: syn::Visibility,
     pub(crate) ty: syn::Type,
+    /// the other attributes of the field (doc comments, serde attributes, ...), kept on the expanded field
+    pub(crate) attrs: Vec<Attribute>,
+    /// the id field, if it is not named `id`: #[musty(id)], with an optional strategy to generate the id: #[musty(id(generate = "uuid_v7"))]
     #[darling(default)]
-    pub(crate) id: bool,
+    pub(crate) id: Option<MetaModelFieldId>,
     /// skip a field: #[musty(skip)]
     #[darling(default)]
     pub(crate) skip: bool,
     /// rename a field: #[musty(rename = "new_field_name")]
     #[darling(default)]
     pub(crate) rename: Option<String>,
+    /// use an integer field as the version of the model, for optimistic concurrency control: #[musty(version)]
+    #[darling(default)]
+    pub(crate) version: bool,
+    /// set the field to the current time when the model is first saved: #[musty(created_at)]
+    #[darling(default)]
+    pub(crate) created_at: bool,
+    /// set the field to the current time whenever the model is saved: #[musty(updated_at)]
+    #[darling(default)]
+    pub(crate) updated_at: bool,
+    /// the soft delete marker of a model with #[model(soft_delete)], if it is not named `deleted_at`: #[musty(deleted_at)]
+    #[darling(default)]
+    pub(crate) deleted_at: bool,
+    /// validations run before the model is saved: #[musty(validate(...))]
+    #[darling(default)]
+    pub(crate) validate: Option<FieldValidation>,
     /// mongo-specific attributes on a field:
     /// #[musty(mongo(...))]
     #[darling(default)]
     pub(crate) mongo: Option<MustyMongoFieldAttrs>,
+    /// GraphQL attributes on a field, for models with #[model(graphql)]:
+    /// #[musty(graphql(skip, name = "..."))]
+    #[darling(default)]
+    pub(crate) graphql: Option<MustyGraphqlFieldAttrs>,
+    /// the name serde stores the field with: from `#[serde(rename = "...")]` on the field,
+    /// or the `#[serde(rename_all = "...")]` rule of the model (see `resolve_serde_names`)
+    #[darling(skip)]
+    pub(crate) serde_name: Option<String>,
+}
+
+/// The id attribute of a field: #[musty(id)] or #[musty(id(generate = "..."))]
+#[derive(Default)]
+pub(crate) struct MetaModelFieldId {
+    /// the strategy generating the id when a model without an id is saved, see `musty::generate`
+    pub(crate) generate: Option<String>,
+}
+
+impl FromMeta for MetaModelFieldId {
+    fn from_word() -> darling::Result<Self> {
+        Ok(Self::default())
+    }
+
+    fn from_list(items: &[NestedMeta]) -> darling::Result<Self> {
+        #[derive(FromMeta)]
+        struct IdArgs {
+            #[darling(default)]
+            generate: Option<String>,
+        }
+
+        let args = IdArgs::from_list(items)?;
+        Ok(Self {
+            generate: args.generate,
+        })
+    }
+}
+
+impl MetaModelField {
+    /// Whether this is the id field of the model: named `id`, or with the attribute #[musty(id)]
+    pub(crate) fn is_id(&self) -> bool {
+        self.id.is_some() || self.ident == Some(Ident::new("id", Span::call_site()))
+    }
+
+    /// The name this field is stored with in the database: `_id` for the id field of MongoDB models,
+    /// or the `#[musty(rename)]` name, or the name serde renames the field to.
+    /// Returns `None` if the field is not stored
+    pub(crate) fn stored_name(&self, is_mongo: bool) -> Option<String> {
+        match (self.skip, self.is_id(), is_mongo) {
+            (true, _, _) | (false, true, false) => None,
+            (false, true, true) => Some(String::from("_id")),
+            (false, false, _) => Some(
+                self.rename
+                    .clone()
+                    .or_else(|| self.serde_name.clone())
+                    .unwrap_or_else(|| self.ident.as_ref().unwrap().to_string()),
+            ),
+        }
+    }
 }
 
-/// The root derive type for a model struct
+/// The root derive type for a model struct, or a model enum (see `enum_model`)
 #[derive(FromDeriveInput)]
-#[darling(attributes(model), forward_attrs(allow, doc, cfg))]
+#[darling(attributes(model), forward_attrs)]
 pub(crate) struct MetaModelDerive {
     pub(crate) ident: Ident,
     pub(crate) vis: Visibility,
-    pub(crate) data: darling::ast::Data<darling::util::Ignored, MetaModelField>,
+    pub(crate) generics: Generics,
+    /// the other attributes of the model (derives, doc comments, serde attributes, ...), kept on the expanded model
+    pub(crate) attrs: Vec<Attribute>,
+    pub(crate) data: darling::ast::Data<MetaModelVariant, MetaModelField>,
+}
+
+/// The items of the `#[serde(...)]` attributes
+fn serde_items(attrs: &[Attribute]) -> impl Iterator<Item = NestedMeta> + '_ {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+}
+
+/// The string value of `key` in the `#[serde(...)]` attributes, ex: `rename` in `#[serde(rename = "name")]`
+pub(crate) fn serde_attr(attrs: &[Attribute], key: &str) -> Option<String> {
+    serde_items(attrs).find_map(|nested| match nested {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(value),
+            ..
+        })) if path.is_ident(key) => Some(value.value()),
+        _ => None,
+    })
+}
+
+/// Expands `#[serde(bound = "")]` for a generic model, unless it sets its own bounds:
+/// the `Model` trait already requires the generic parameters to be bounded by the where clause of the model
+/// (ex: `T: Serialize + DeserializeOwned + Send + Sync + Unpin`), so the bounds serde would add are redundant,
+/// and ambiguous with `DeserializeOwned` bounds
+pub(crate) fn expand_serde_bound(
+    generics: &Generics,
+    attrs: &[Attribute],
+) -> proc_macro2::TokenStream {
+    let bounded = serde_items(attrs).any(|nested| match nested {
+        NestedMeta::Meta(meta) => meta.path().is_ident("bound"),
+        NestedMeta::Lit(_) => false,
+    });
+    match generics.params.is_empty() || bounded {
+        true => quote! {},
+        false => quote! { #[serde(bound = "")] },
+    }
+}
+
+/// Applies a `#[serde(rename_all = "...")]` rule to a field or variant name
+pub(crate) fn apply_rename_all(ident: &Ident, rule: &str) -> String {
+    rename_all(&ident.to_string(), rule).unwrap_or_else(|| {
+        abort!(
+            ident.span(),
+            "Unknown `rename_all` rule `{}`, expected one of: lowercase, UPPERCASE, PascalCase, camelCase, snake_case, SCREAMING_SNAKE_CASE, kebab-case, SCREAMING-KEBAB-CASE",
+            rule
+        )
+    })
+}
+
+/// Sets the names serde stores the fields with, from their `#[serde(rename)]` attributes
+/// or the `#[serde(rename_all)]` rule of the model (or of the variant, for the fields of an enum variant)
+pub(crate) fn resolve_serde_names<'a>(
+    fields: impl IntoIterator<Item = &'a mut MetaModelField>,
+    rule: Option<&str>,
+) {
+    for field in fields.into_iter().filter(|field| !field.is_id()) {
+        field.serde_name = serde_attr(&field.attrs, "rename")
+            .or_else(|| rule.map(|rule| apply_rename_all(field.ident.as_ref().unwrap(), rule)));
+    }
+}
+
+/// Marks the field named `deleted_at` as the soft delete marker of a model with #[model(soft_delete)],
+/// unless another field has the attribute #[musty(deleted_at)]
+fn resolve_soft_delete_marker(fields: &mut [MetaModelField]) {
+    if fields.iter().any(|field| field.deleted_at) {
+        return;
+    }
+    if let Some(field) = fields
+        .iter_mut()
+        .find(|field| field.ident.as_ref().is_some_and(|ident| ident == "deleted_at"))
+    {
+        field.deleted_at = true;
+    }
+}
+
+/// Expands the derives the model needs (Debug, serde::Serialize, serde::Deserialize) which are not derived by the model already
+pub(crate) fn expand_derives(attrs: &[Attribute]) -> proc_macro2::TokenStream {
+    let derived = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .filter_map(|path| {
+            path.segments
+                .last()
+                .map(|segment| segment.ident.to_string())
+        })
+        .collect::<Vec<_>>();
+
+    let derives = [
+        ("Debug", quote! { Debug }),
+        ("Serialize", quote! { serde::Serialize }),
+        ("Deserialize", quote! { serde::Deserialize }),
+    ]
+    .into_iter()
+    .filter(|(name, _)| !derived.iter().any(|derived| derived == name))
+    .map(|(_, derive)| derive)
+    .collect::<Vec<_>>();
+
+    match derives.is_empty() {
+        true => quote! {},
+        false => quote! { #[derive(#(#derives),*)] },
+    }
+}
+
+/// The where clause of the trait implementations of a model: the where clause of the model, and `Self: Sized`
+pub(crate) fn expand_where_sized(generics: &Generics) -> proc_macro2::TokenStream {
+    let predicates = generics
+        .where_clause
+        .iter()
+        .flat_map(|where_clause| where_clause.predicates.iter());
+    quote! { where #(#predicates,)* Self: Sized }
+}
+
+/// Get the `id` field (or field with attribute #[musty(id)]) of a model, and its type
+pub(crate) fn get_id_field<'a>(
+    ident: &Ident,
+    fields: impl IntoIterator<Item = &'a MetaModelField>,
+) -> (&'a MetaModelField, Path) {
+    let id_field = fields.into_iter().find(|field| {
+        field.id.is_some() || field.ident == Some(Ident::new("id", Span::call_site()))
+    });
+
+    if id_field.is_none() {
+        abort!(ident.span(), "{} must have an `id` field", ident);
+    }
+
+    let id_field = id_field.unwrap();
+
+    let path = match &id_field.ty {
+        Type::Path(TypePath { path, .. }) => path,
+        _ => {
+            abort!(ident.span(), "{} `id` field must be path", ident)
+        }
+    };
+
+    (id_field, path.clone())
+}
+
+/// Expands the fields of a model struct (or of a variant of a model enum) other than the id field, with their serde attributes,
+/// and their GraphQL attributes if the model has #[model(graphql)].
+/// Fields of enum variants can not have a visibility
+pub(crate) fn expand_struct_fields<'a>(
+    fields: impl IntoIterator<Item = &'a MetaModelField>,
+    with_vis: bool,
+    graphql: bool,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .into_iter()
+        .filter(|field| !field.is_id())
+        .map(|field| {
+            let ident = field.ident.as_ref().unwrap();
+            let ty = &field.ty;
+            let vis = with_vis.then_some(&field.vis);
+            let mut field_attr = quote! {};
+            if field.skip {
+                field_attr = quote! { #[serde(skip)] }
+            } else if let Some(rename) = field.rename.as_ref() {
+                field_attr = quote! { 
+                    #[serde(rename = #rename)]
+                }
+            }
+            let attrs = &field.attrs;
+            let graphql_attrs = graphql.then(|| expand_graphql_field_attrs(field));
+            quote! {
+                #(#attrs)*
+                #field_attr
+                #graphql_attrs
+                #vis #ident: #ty
+            }
+        })
+        .collect()
+}
+
+/// Expands the `generate_id` function of the `Model` trait, if the id field has the attribute #[musty(id(generate = "..."))]
+pub(crate) fn expand_generate_id(id_field: &MetaModelField) -> Option<proc_macro2::TokenStream> {
+    let strategy = id_field.id.as_ref()?.generate.as_ref()?;
+    let generate = match strategy.as_str() {
+        "uuid_v4" => quote! { musty::generate::uuid_v4() },
+        "uuid_v7" => quote! { musty::generate::uuid_v7(db) },
+        "ulid" => quote! { musty::generate::ulid(db) },
+        "snowflake" => quote! { musty::generate::snowflake(db) },
+        "sequence" => quote! { musty::generate::sequence::<Self, B>(db).await? },
+        _ => abort!(
+            id_field.ident.as_ref().unwrap().span(),
+            "Unknown id strategy `{}`, expected one of: uuid_v4, uuid_v7, ulid, snowflake, sequence",
+            strategy
+        ),
+    };
+
+    Some(quote! {
+        async fn generate_id<B>(&mut self, db: &musty::prelude::Musty<B>) -> musty::Result<()>
+        where
+            Self: musty::prelude::Context<Self::Id, B> + 'static,
+            B: musty::prelude::Backend,
+        {
+            if self.id().is_none() {
+                self.set_id(musty::prelude::Id::from(#generate));
+            }
+            Ok(())
+        }
+    })
+}
+
+/// Expands the serde attributes of the id field of a model
+pub(crate) fn expand_id_attr(args: &MetaModelAttr) -> proc_macro2::TokenStream {
+    match args.mongo.as_ref() {
+        Some(_) => quote! { #[serde(rename = "_id", skip_serializing_if = "musty::prelude::Id::is_none")] },
+        None => quote! { #[serde(skip)] },
+    }
+}
+
+/// Expands the typed field paths of a model from the field names, visibilities, stored names and types:
+/// a `{Model}Fields` struct holding a `musty::prelude::Field` for every field, and a `fields()` function on the model returning it
+/// The paths are implemented by hand rather than derived, so they are `Copy` and `Debug` whatever the generic parameters of the model
+pub(crate) fn expand_field_paths(
+    ident: &Ident,
+    vis: &Visibility,
+    generics: &Generics,
+    fields: &[(&Ident, &Visibility, String, proc_macro2::TokenStream)],
+) -> proc_macro2::TokenStream {
+    let fields_ident = format_ident!("{}Fields", ident);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let declarations = fields.iter().map(|(field_ident, vis, _, ty)| {
+        quote! { #vis #field_ident: musty::prelude::Field<#ident #ty_generics, #ty> }
+    });
+
+    let values = fields.iter().map(|(field_ident, _, path, _)| {
+        quote! { #field_ident: musty::prelude::Field::new(#path) }
+    });
+
+    let field_idents = fields.iter().map(|(field_ident, ..)| field_ident);
+    let fields_name = fields_ident.to_string();
+
+    let doc = format!("Typed field paths for [`{}`], see `musty::prelude::Field`.", ident);
+
+    quote! {
+        #[doc = #doc]
+        #vis struct #fields_ident #impl_generics #where_clause {
+            #(#declarations),*
+        }
+
+        #[automatically_derived]
+        impl #impl_generics Clone for #fields_ident #ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics Copy for #fields_ident #ty_generics #where_clause {}
+
+        #[automatically_derived]
+        impl #impl_generics std::fmt::Debug for #fields_ident #ty_generics #where_clause {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_struct(#fields_name)
+                    #(.field(stringify!(#field_idents), &self.#field_idents))*
+                    .finish()
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Typed paths to the stored fields of this model, for use in filters, sorts, projections and updates
+            #vis const fn fields() -> #fields_ident #ty_generics {
+                #fields_ident {
+                    #(#values),*
+                }
+            }
+        }
+    }
 }
 
 impl MetaModelDerive {
@@ -56,92 +433,430 @@ impl MetaModelDerive {
             _ => abort!(ident.span(), "Model must be a struct"),
         };
 
-        let id_field = fields.iter().find(|field| {
-            field.id || field.ident == Some(Ident::new("id", Span::call_site()))
-        });
+        let (id_field, path) = get_id_field(ident, fields.iter());
+        (id_field.vis.clone(), path)
+    }
 
-        if id_field.is_none() {
-            abort!(ident.span(), "{} must have an `id` field", ident);
+    /// The fields of the model struct, or the fields of every variant of the model enum
+    pub(crate) fn all_fields(&self) -> Vec<&MetaModelField> {
+        match &self.data {
+            darling::ast::Data::Struct(fields) => fields.iter().collect(),
+            darling::ast::Data::Enum(variants) => variants
+                .iter()
+                .flat_map(|variant| variant.fields.iter())
+                .collect(),
         }
-
-        let id_field = id_field.unwrap();
-
-        let path = match &id_field.ty {
-            Type::Path(TypePath { path, .. }) => path,
-            _ => {
-                abort!(ident.span(), "{} `id` field must be path", ident)
-            }
-        };
-
-        return (id_field.vis.clone(), path.clone());
     }
 
     /// Re-creates the struct for the Model that had the attribute #[model(...)] macro on it
     /// This edits the id type to be `musty::prelude::Id<Self, #id_type>` and adds necessary serde attributes,
-    /// and required derives (Debug, serde::Serialize, serde::Deserialize)
+    /// and required derives (Debug, serde::Serialize, serde::Deserialize) which the struct does not derive already.
+    /// The generics, attributes and doc comments of the struct and its fields are kept as they are
+    /// The struct of a variant of an enum model is tagged with its discriminator, like the variant is in the enum
     fn create_model_struct(
         &self,
         id_vis: &Visibility,
         id_type: &Path,
         args: &MetaModelAttr,
+        variant_of: Option<&VariantOf>,
     ) -> proc_macro2::TokenStream {
         let ident = &self.ident;
         let data = &self.data;
         let vis = &self.vis;
-        let mut id_attr = quote! { #[serde(skip)] };
+        let generics = &self.generics;
+        let where_clause = &generics.where_clause;
+        let attrs = &self.attrs;
+        let derives = expand_derives(attrs);
+        let serde_bound = expand_serde_bound(generics, attrs);
+        let id_attr = expand_id_attr(args);
+
+        let fields = match data {
+            darling::ast::Data::Struct(fields) => fields,
+            _ => abort!(ident.span(), "Model must be a struct"),
+        };
+
+        let (id_field, _) = get_id_field(ident, fields.iter());
+        let id_attrs = &id_field.attrs;
+        let id_graphql_attrs = args
+            .graphql
+            .as_ref()
+            .map(|_| expand_graphql_field_attrs(id_field));
+        let fields = expand_struct_fields(fields.iter(), true, args.graphql.is_some());
+        let graphql_attrs = args
+            .graphql
+            .as_ref()
+            .map(|graphql| expand_graphql_object_attrs(self, graphql));
 
-        if args.mongo.as_ref().is_some() {
-            id_attr = quote! { #[serde(rename = "_id", skip_serializing_if = "musty::prelude::Id::is_none")] };
+        let variant_attrs = variant_of.map(|variant_of| {
+            let (model, field, value) = (&variant_of.model, &variant_of.field, &variant_of.value);
+            let doc = format!(
+                "The `{}` variant of [`{}`], stored in the same collection with `{}` set to `\"{}\"`.",
+                variant_of.variant, model, field, value
+            );
+            quote! {
+                #[doc = #doc]
+                #[serde(tag = #field, rename = #value)]
+            }
+        });
+
+        quote! {
+            #derives
+            #graphql_attrs
+            #(#attrs)*
+            #serde_bound
+            #variant_attrs
+            #vis struct #ident #generics #where_clause {
+                #(#id_attrs)*
+                #id_attr
+                #id_graphql_attrs
+                #id_vis id: musty::prelude::Id<Self, #id_type>,
+                #(#fields),*
+            }
         }
+    }
 
-        let fields = match data {
+    /// Expands the typed field paths of the model: a `{Model}Fields` struct holding a `musty::prelude::Field` for every stored field,
+    /// and a `fields()` function on the model returning it.
+    /// Paths use the name a field is stored with: `#[musty(rename)]` is respected, and the id field is stored as `_id` for MongoDB models
+    /// (it is not stored otherwise, so it has no path)
+    fn expand_fields(&self, id_type: &Path, args: &MetaModelAttr) -> proc_macro2::TokenStream {
+        let ident = &self.ident;
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+
+        let fields = match &self.data {
             darling::ast::Data::Struct(fields) => fields,
             _ => abort!(ident.span(), "Model must be a struct"),
         };
 
         let fields = fields
             .iter()
-            .filter(|field| { !field.id && field.ident != Some(Ident::new("id", Span::call_site())) })
-            .map(|field| {
-                let ident = field.ident.as_ref().unwrap();
-                let ty = &field.ty;
-                let vis = &field.vis;
-                let mut field_attr = quote! {};
-                if field.skip {
-                    field_attr = quote! { #[serde(skip)] }
-                } else if let Some(rename) = field.rename.as_ref() {
-                    field_attr = quote! { 
-                        #[serde(rename = #rename)]
-                    }
-                }
-                quote! {
-                    #field_attr
-                    #vis #ident: #ty
+            .filter_map(|field| {
+                let field_ident = field.ident.as_ref().unwrap();
+                let path = field.stored_name(args.mongo.is_some())?;
+                let ty = if field.is_id() {
+                    quote! { musty::prelude::Id<#ident #ty_generics, #id_type> }
+                } else {
+                    let ty = &field.ty;
+                    quote! { #ty }
+                };
+                Some((field_ident, &field.vis, path, ty))
+            })
+            .collect::<Vec<_>>();
+
+        expand_field_paths(ident, &self.vis, &self.generics, &fields)
+    }
+
+    /// Expands the version of the model, if a field has the attribute #[musty(version)]:
+    /// the stored name of the field, and the `version` & `set_version` functions of the `Model` trait
+    fn expand_version(&self, args: &MetaModelAttr) -> Option<proc_macro2::TokenStream> {
+        let ident = &self.ident;
+
+        let fields = match &self.data {
+            darling::ast::Data::Struct(fields) => fields,
+            _ => abort!(ident.span(), "Model must be a struct"),
+        };
+
+        let mut versions = fields.iter().filter(|field| field.version);
+        let field = versions.next()?;
+        let field_ident = field.ident.as_ref().unwrap();
+        if let Some(other) = versions.next() {
+            abort!(
+                other.ident.as_ref().unwrap().span(),
+                "{} can only have one `#[musty(version)]` field",
+                ident
+            );
+        }
+        if field.is_id() {
+            abort!(field_ident.span(), "The id field can not be the version");
+        }
+        let name = field.stored_name(args.mongo.is_some()).unwrap_or_else(|| {
+            abort!(field_ident.span(), "The version field can not be skipped")
+        });
+
+        Some(quote! {
+            const VERSION_FIELD: Option<&'static str> = Some(#name);
+
+            fn version(&self) -> musty::Result<Option<i64>> {
+                i64::try_from(self.#field_ident)
+                    .map(Some)
+                    .map_err(|_| musty::MustyError::VersionOutOfRange)
+            }
+
+            fn set_version(&mut self, version: i64) -> musty::Result<()> {
+                self.#field_ident = version
+                    .try_into()
+                    .map_err(|_| musty::MustyError::VersionOutOfRange)?;
+                Ok(())
+            }
+        })
+    }
+
+    /// Expands the `set_timestamps` function of the `Model` trait, if fields have the attributes #[musty(created_at)] or #[musty(updated_at)].
+    /// The timestamps of MongoDB models must be stored as BSON datetimes, which is checked with the `BsonTimestamp` trait
+    fn expand_timestamps(&self, args: &MetaModelAttr) -> Option<proc_macro2::TokenStream> {
+        let ident = &self.ident;
+
+        let fields = match &self.data {
+            darling::ast::Data::Struct(fields) => fields,
+            _ => abort!(ident.span(), "Model must be a struct"),
+        };
+
+        let timestamp_field = |attr: &str, is_timestamp: fn(&MetaModelField) -> bool| {
+            let mut timestamps = fields.iter().filter(|field| is_timestamp(field));
+            let field = timestamps.next()?;
+            if let Some(other) = timestamps.next() {
+                abort!(
+                    other.ident.as_ref().unwrap().span(),
+                    "{} can only have one `#[musty({})]` field",
+                    ident,
+                    attr
+                );
+            }
+            if field.is_id() || field.skip {
+                abort!(
+                    field.ident.as_ref().unwrap().span(),
+                    "The `#[musty({})]` field must be a stored field",
+                    attr
+                );
+            }
+            field.ident.as_ref()
+        };
+
+        let created_at_field = timestamp_field("created_at", |field| field.created_at);
+        let updated_at_field = timestamp_field("updated_at", |field| field.updated_at);
+        if created_at_field.is_none() && updated_at_field.is_none() {
+            return None;
+        }
+
+        let bson_timestamps = args.mongo.is_some().then(|| {
+            let fields = created_at_field.iter().chain(updated_at_field.iter());
+            quote! {
+                fn bson_timestamp<T: musty::prelude::BsonTimestamp>(_: &T) {}
+                #(bson_timestamp(&self.#fields);)*
+            }
+        });
+        let created_at = created_at_field.map(|field| {
+            quote! {
+                if inserting {
+                    self.#field = musty::prelude::Timestamp::from_system_time(now);
                 }
+            }
+        });
+        let has_created_at = created_at.is_some().then(|| {
+            quote! {
+                const HAS_CREATED_AT: bool = true;
+            }
+        });
+        let updated_at = updated_at_field.map(|field| {
+            quote! {
+                self.#field = musty::prelude::Timestamp::from_system_time(now);
+            }
+        });
+
+        Some(quote! {
+            #has_created_at
+
+            fn set_timestamps(&mut self, now: std::time::SystemTime, inserting: bool) {
+                #bson_timestamps
+                #created_at
+                #updated_at
+            }
+        })
+    }
+
+    /// Expands the soft delete marker of the model, if the model has the attribute #[model(soft_delete)]:
+    /// the stored name of the marker field, and the `is_deleted`, `deleted_at` & `set_deleted_at` functions of the `Model` trait.
+    /// Like the timestamps, the marker of MongoDB models must be stored as a BSON datetime
+    fn expand_soft_delete(&self, args: &MetaModelAttr) -> Option<proc_macro2::TokenStream> {
+        let ident = &self.ident;
+
+        let fields = match &self.data {
+            darling::ast::Data::Struct(fields) => fields,
+            _ => abort!(ident.span(), "Model must be a struct"),
+        };
+
+        // the field named `deleted_at` is marked as the marker of soft delete models, see `resolve_soft_delete_marker`
+        let marker = fields.iter().find(|field| field.deleted_at);
+
+        if !args.soft_delete {
+            if let Some(field) = marker {
+                abort!(
+                    field.ident.as_ref().unwrap().span(),
+                    "`#[musty(deleted_at)]` requires `#[model(soft_delete)]` on {}",
+                    ident
+                );
+            }
+            return None;
+        }
+
+        let field = marker.unwrap_or_else(|| {
+            abort!(
+                ident.span(),
+                "{} has `#[model(soft_delete)]`, so it must have a `deleted_at: Option<_>` field (or a field with `#[musty(deleted_at)]`)",
+                ident
+            )
+        });
+        let field_ident = field.ident.as_ref().unwrap();
+        let name = field
+            .stored_name(args.mongo.is_some())
+            .filter(|_| !field.is_id())
+            .unwrap_or_else(|| {
+                abort!(field_ident.span(), "The soft delete marker must be a stored field")
             });
+        let bson_timestamp = args.mongo.is_some().then(|| {
+            quote! {
+                fn bson_timestamp<T: musty::prelude::BsonTimestamp>(_: &T) {}
+                bson_timestamp(&self.#field_ident);
+            }
+        });
 
-        quote! {
-            #[derive(Debug, serde::Serialize, serde::Deserialize)]
-            #vis struct #ident {
-                #id_attr
-                #id_vis id: musty::prelude::Id<Self, #id_type>,
-                #(#fields),*
+        Some(quote! {
+            const SOFT_DELETE_FIELD: Option<&'static str> = Some(#name);
+
+            fn is_deleted(&self) -> bool {
+                !musty::prelude::Timestamp::is_unset(&self.#field_ident)
+            }
+
+            fn deleted_at(&self) -> Option<std::time::SystemTime> {
+                musty::prelude::Timestamp::to_system_time(&self.#field_ident)
+            }
+
+            fn set_deleted_at(&mut self, deleted_at: Option<std::time::SystemTime>) {
+                #bson_timestamp
+                self.#field_ident = deleted_at.map(musty::prelude::Timestamp::from_system_time);
+            }
+        })
+    }
+
+    /// Expands the `run_hook` function of the `Model` trait, dispatching to the `ModelHooks` implementation of the model,
+    /// if the model has the attribute #[model(hooks)]
+    fn expand_hooks(&self, args: &MetaModelAttr) -> Option<proc_macro2::TokenStream> {
+        if !args.hooks {
+            return None;
+        }
+
+        Some(quote! {
+            const HOOKS: bool = true;
+
+            async fn run_hook<B: musty::prelude::Backend>(
+                &mut self,
+                db: &musty::prelude::Musty<B>,
+                hook: musty::prelude::Hook,
+            ) -> musty::Result<()> {
+                match hook {
+                    musty::prelude::Hook::BeforeSave => <Self as musty::prelude::ModelHooks>::before_save(self, db).await,
+                    musty::prelude::Hook::AfterSave => <Self as musty::prelude::ModelHooks>::after_save(self, db).await,
+                    musty::prelude::Hook::BeforeDelete => <Self as musty::prelude::ModelHooks>::before_delete(self, db).await,
+                    musty::prelude::Hook::AfterDelete => <Self as musty::prelude::ModelHooks>::after_delete(self, db).await,
+                    musty::prelude::Hook::AfterLoad => <Self as musty::prelude::ModelHooks>::after_load(self, db).await,
+                }
             }
+        })
+    }
+
+    /// Expands the `validate` function of the `Model` trait, if fields have the attribute #[musty(validate(...))]
+    fn expand_validate(&self, args: &MetaModelAttr) -> Option<proc_macro2::TokenStream> {
+        let ident = &self.ident;
+
+        let fields = match &self.data {
+            darling::ast::Data::Struct(fields) => fields,
+            _ => abort!(ident.span(), "Model must be a struct"),
+        };
+
+        let checks = fields
+            .iter()
+            .filter_map(|field| {
+                let validate = field.validate.as_ref()?;
+                let field_ident = field.ident.as_ref().unwrap();
+                let name = field
+                    .stored_name(args.mongo.is_some())
+                    .unwrap_or_else(|| field_ident.to_string());
+                Some(validate.expand(field_ident, &name))
+            })
+            .collect::<Vec<_>>();
+
+        if checks.is_empty() {
+            return None;
+        }
+
+        Some(quote! {
+            fn validate(&self) -> std::result::Result<(), musty::validation::ValidationErrors> {
+                let mut errors = musty::validation::ValidationErrors::new();
+                #(#checks)*
+                errors.into_result()
+            }
+        })
+    }
+
+    /// Expands the model struct (or the model enum, see `enum_model`) and its trait implementations
+    pub fn expand(mut self, args: MetaModelAttr) -> proc_macro::TokenStream {
+        let rule = serde_attr(&self.attrs, "rename_all");
+        match &mut self.data {
+            darling::ast::Data::Struct(fields) => {
+                resolve_serde_names(fields.fields.iter_mut(), rule.as_deref());
+                if args.soft_delete {
+                    resolve_soft_delete_marker(&mut fields.fields);
+                }
+            }
+            // `rename_all` on an enum renames its variants, the fields of a variant are renamed by `rename_all` on the variant
+            darling::ast::Data::Enum(variants) => {
+                for variant in variants.iter_mut() {
+                    let rule = serde_attr(&variant.attrs, "rename_all");
+                    resolve_serde_names(variant.fields.fields.iter_mut(), rule.as_deref());
+                }
+            }
+        }
+
+        match self.data {
+            darling::ast::Data::Enum(_) => super::enum_model::expand_enum_model(self, args),
+            darling::ast::Data::Struct(_) => self.expand_model(&args, None),
         }
         .into()
     }
 
     /// Expands the model struct and the `Model` trait implementation for the model struct
     /// If the `mongo` attribute is present, this also expands the `MongoModel` trait implementation
-    pub fn expand(self, args: MetaModelAttr) -> proc_macro::TokenStream {
+    /// `variant_of` is set for the struct of a variant of an enum model
+    pub(crate) fn expand_model(
+        &self,
+        args: &MetaModelAttr,
+        variant_of: Option<&VariantOf>,
+    ) -> proc_macro2::TokenStream {
         let ident = &self.ident;
+        let (impl_generics, ty_generics, _) = self.generics.split_for_impl();
+        let where_sized = expand_where_sized(&self.generics);
 
         let (model_id_vis, model_id_type) = self.get_model_id();
-        let model_struct = self.create_model_struct(&model_id_vis, &model_id_type, &args);
+        let model_struct = self.create_model_struct(&model_id_vis, &model_id_type, args, variant_of);
+        let fields = self.expand_fields(&model_id_type, args);
+        let version = self.expand_version(args);
+        let timestamps = self.expand_timestamps(args);
+        let soft_delete = self.expand_soft_delete(args);
+        let hooks = self.expand_hooks(args);
+        let validate = self.expand_validate(args);
+        let generate_id = self
+            .all_fields()
+            .into_iter()
+            .find(|field| field.is_id())
+            .and_then(expand_generate_id);
+        let discriminator = variant_of.map(|variant_of| {
+            let (model, field, value) = (&variant_of.model, &variant_of.field, &variant_of.value);
+            quote! {
+                const DISCRIMINATOR: Option<(&'static str, &'static str)> = Some((#field, #value));
+
+                fn collection_type() -> std::any::TypeId
+                where
+                    Self: 'static,
+                {
+                    std::any::TypeId::of::<#model #ty_generics>()
+                }
+            }
+        });
 
         let mut model = quote! {
+            #[musty::prelude::async_trait]
             #[automatically_derived]
-            impl musty::prelude::Model for #ident where Self: Sized {
+            impl #impl_generics musty::prelude::Model for #ident #ty_generics #where_sized {
                 type Id = #model_id_type;
 
                 fn id(&self) -> &Id<Self, #model_id_type> {
@@ -151,13 +866,27 @@ impl MetaModelDerive {
                 fn set_id(&mut self, id: Id<Self, #model_id_type>) {
                     self.id = id;
                 }
+
+                #version
+
+                #timestamps
+
+                #soft_delete
+
+                #hooks
+
+                #validate
+
+                #generate_id
+
+                #discriminator
             }
         };
 
         if let Some(mongo_attrs) = args.mongo.as_ref() {
-            let mongo_model = super::mongo_model::expand_mongo_model(&self, &mongo_attrs);
+            let mongo_model = super::mongo_model::expand_mongo_model(self, mongo_attrs, variant_of);
 
-            let mongo_fields_impl = super::mongo_model::expand_mongo_fields_impl(&self);
+            let mongo_fields_impl = super::mongo_model::expand_mongo_fields_impl(self);
 
             model = quote! {
                 #model
@@ -168,10 +897,17 @@ impl MetaModelDerive {
             };
         }
 
+        let graphql_input = args
+            .graphql
+            .as_ref()
+            .filter(|_| variant_of.is_none())
+            .map(|graphql| expand_graphql_input(self, graphql));
+
         quote! {
             #model_struct
+            #fields
             #model
+            #graphql_input
         }
-        .into()
     }
 }
@@ -1,2 +1,5 @@
+pub(crate) mod enum_model;
+pub(crate) mod graphql_model;
 pub(crate) mod meta_model;
-pub(crate) mod mongo_model;
\ No newline at end of file
+pub(crate) mod mongo_model;
+pub(crate) mod validate;
@@ -2,53 +2,352 @@ use darling::FromMeta;
 use quote::{quote, format_ident};
 use syn::Ident;
 use crate::util::string::{ToPlural, ToTableCase};
-use super::meta_model::MetaModelDerive;
+use super::enum_model::VariantOf;
+use super::meta_model::{expand_where_sized, MetaModelDerive, MetaModelField};
 use proc_macro_error::abort;
 
 #[derive(Default, FromMeta)]
 #[darling(default)]
 pub(crate) struct MustyMongoFieldAttrs {
     pub(crate) get_by: bool,
+    /// index the field: #[musty(mongo(index))]
+    pub(crate) index: bool,
+    /// index the field with a unique index: #[musty(mongo(unique))]
+    pub(crate) unique: bool,
+    /// index the field with a sparse index: #[musty(mongo(sparse))]
+    pub(crate) sparse: bool,
+    /// include the field in the text index of the collection: #[musty(mongo(text))]
+    pub(crate) text: bool,
+    /// index the field with a TTL index, ex: #[musty(mongo(ttl = "30d"))]
+    /// a number of seconds, optionally suffixed with a unit: s, m, h, d
+    pub(crate) ttl: Option<String>,
+}
+
+/// A compound index on a model struct:
+/// #[model(mongo(index(keys = "name, -age", unique, sparse, name = "name_age")))]
+#[derive(FromMeta)]
+pub(crate) struct ModelMongoIndex {
+    /// comma-separated field names, prefixed with `-` for a descending key
+    pub(crate) keys: String,
+    #[darling(default)]
+    pub(crate) unique: bool,
+    #[darling(default)]
+    pub(crate) sparse: bool,
+    #[darling(default)]
+    pub(crate) name: Option<String>,
 }
 
 
 /// MongoDB-specific attributes for a model struct:
-/// #[model(mongo(collection = "users"))]
+/// #[model(mongo(collection = "users", read_concern = "majority", write_concern = "majority", journal = true, read_preference = "secondaryPreferred"))]
 #[derive(Default, FromMeta)]
 #[darling(default)]
 pub(crate) struct ModelMongoAttrs {
     pub(crate) collection: Option<String>,
+    /// read concern level: "local", "majority", "linearizable", "available", "snapshot"
+    pub(crate) read_concern: Option<String>,
+    /// write concern acknowledgment: "majority", a number of nodes, or a custom tag set name
+    pub(crate) write_concern: Option<String>,
+    /// whether the write concern requires writes to be journaled
+    pub(crate) journal: Option<bool>,
+    /// read preference: "primary", "primaryPreferred", "secondary", "secondaryPreferred", "nearest"
+    pub(crate) read_preference: Option<String>,
+    /// compound indexes, can be repeated
+    #[darling(multiple, rename = "index")]
+    pub(crate) indexes: Vec<ModelMongoIndex>,
 }
 
 /// Expands the `MongoModel` for a model struct
 /// This sets the collection name, based on the optional attribute value:
 /// `#[model(mongo(collection = "users"))]` or the default value of the table-cased & pluralized struct name
 /// (ex: `MyStruct` -> `my_structs`)
+/// and the read concern, write concern and selection criteria, if set.
+/// The declared indexes are expanded, and the model is registered for `Musty::sync_all_indexes`
+/// (generic models can not be registered, their indexes are synced with `Musty::sync_indexes` for every type they are used with)
+/// The variants of an enum model are stored in the collection of the enum model, which declares the indexes of the collection
 pub(crate) fn expand_mongo_model(
     meta: &MetaModelDerive,
     mongo: &ModelMongoAttrs,
+    variant_of: Option<&VariantOf>,
 ) -> proc_macro2::TokenStream {
     let ident = &meta.ident;
+    let (impl_generics, ty_generics, _) = meta.generics.split_for_impl();
+    let where_sized = expand_where_sized(&meta.generics);
 
     let collection_name = mongo.collection.clone().unwrap_or_else(|| {
-        ident
+        variant_of
+            .map_or(ident, |variant_of| &variant_of.model)
             .to_string()
             .to_table_case()
             .to_ascii_lowercase()
             .to_plural()
     });
 
+    let read_concern = mongo.read_concern.as_ref().map(|level| {
+        quote! {
+            fn read_concern() -> Option<musty::mongodb::options::ReadConcern> {
+                Some(musty::mongodb::options::ReadConcern::custom(#level.to_string()))
+            }
+        }
+    });
+
+    let write_concern = if mongo.write_concern.is_some() || mongo.journal.is_some() {
+        let w = match mongo.write_concern.as_ref() {
+            Some(w) => match w.parse::<u32>() {
+                Ok(nodes) => {
+                    quote! { Some(musty::mongodb::options::Acknowledgment::Nodes(#nodes)) }
+                }
+                Err(_) => {
+                    quote! { Some(musty::mongodb::options::Acknowledgment::from(#w.to_string())) }
+                }
+            },
+            None => quote! { None },
+        };
+        let journal = match mongo.journal {
+            Some(journal) => quote! { Some(#journal) },
+            None => quote! { None },
+        };
+        Some(quote! {
+            fn write_concern() -> Option<musty::mongodb::options::WriteConcern> {
+                Some(
+                    musty::mongodb::options::WriteConcern::builder()
+                        .w(#w)
+                        .journal(#journal)
+                        .build(),
+                )
+            }
+        })
+    } else {
+        None
+    };
+
+    let selection_criteria = mongo.read_preference.as_ref().map(|read_preference| {
+        let mode = match read_preference.as_str() {
+            "primary" => quote! { Primary },
+            "primaryPreferred" => quote! { PrimaryPreferred { options: Default::default() } },
+            "secondary" => quote! { Secondary { options: Default::default() } },
+            "secondaryPreferred" => quote! { SecondaryPreferred { options: Default::default() } },
+            "nearest" => quote! { Nearest { options: Default::default() } },
+            _ => abort!(
+                ident.span(),
+                "Unknown read preference `{}`, expected one of: primary, primaryPreferred, secondary, secondaryPreferred, nearest",
+                read_preference
+            ),
+        };
+        quote! {
+            fn selection_criteria() -> Option<musty::mongodb::options::SelectionCriteria> {
+                Some(musty::mongodb::options::SelectionCriteria::ReadPreference(
+                    musty::mongodb::options::ReadPreference::#mode,
+                ))
+            }
+        }
+    });
+
+    if variant_of.is_some() {
+        return quote! {
+            #[musty::prelude::async_trait]
+            #[automatically_derived]
+            impl #impl_generics musty::prelude::MongoModel for #ident #ty_generics #where_sized {
+                const COLLECTION_NAME: &'static str = #collection_name;
+
+                #read_concern
+
+                #write_concern
+
+                #selection_criteria
+            }
+        };
+    }
+
+    let indexes = expand_indexes(meta, mongo);
+
+    let registration = meta.generics.params.is_empty().then(|| {
+        quote! {
+            musty::inventory::submit! {
+                musty::backend::IndexRegistration::new::<#ident>()
+            }
+        }
+    });
+
     quote! {
         #[musty::prelude::async_trait]
         #[automatically_derived]
-        impl musty::prelude::MongoModel for #ident where Self: Sized {
+        impl #impl_generics musty::prelude::MongoModel for #ident #ty_generics #where_sized {
             const COLLECTION_NAME: &'static str = #collection_name;
+
+            #read_concern
+
+            #write_concern
+
+            #selection_criteria
+
+            #indexes
         }
+
+        #registration
     }
 }
 
+/// Expands `MongoModel::indexes` from the index attributes on the fields and the compound indexes on the model struct.
+/// All fields with `#[musty(mongo(text))]` share a single text index, as MongoDB allows one text index per collection
+/// The fields of every variant of an enum model are indexed, a field shared by several variants is indexed once
+fn expand_indexes(meta: &MetaModelDerive, mongo: &ModelMongoAttrs) -> Option<proc_macro2::TokenStream> {
+    let ident = &meta.ident;
+    let fields: Vec<&MetaModelField> = meta.all_fields();
+
+    let mut indexes = Vec::new();
+    let mut indexed_fields = Vec::new();
+    let mut text_fields = Vec::new();
+
+    for field in fields.iter() {
+        let attrs = match field.mongo.as_ref() {
+            Some(attrs) => attrs,
+            None => continue,
+        };
+        let field_ident = field.ident.as_ref().unwrap();
+        let name = match field.stored_name(true) {
+            Some(name) => name,
+            None if attrs.index || attrs.unique || attrs.sparse || attrs.text || attrs.ttl.is_some() => {
+                abort!(field_ident.span(), "Skipped field `{}` can not be indexed", field_ident)
+            }
+            None => continue,
+        };
+
+        if indexed_fields.contains(&name) {
+            continue;
+        }
+        indexed_fields.push(name.clone());
+
+        if attrs.text {
+            text_fields.push(name.clone());
+        }
+
+        if attrs.index || attrs.unique || attrs.sparse || attrs.ttl.is_some() {
+            let expire_after = attrs.ttl.as_ref().map(|ttl| {
+                let seconds = parse_ttl(ttl).unwrap_or_else(|| {
+                    abort!(
+                        field_ident.span(),
+                        "Invalid ttl `{}`, expected a number of seconds, optionally suffixed with a unit: s, m, h, d (ex: \"30d\")",
+                        ttl
+                    )
+                });
+                quote! { .expire_after(std::time::Duration::from_secs(#seconds)) }
+            });
+            indexes.push(expand_index(
+                &[(name, quote! { 1_i32 })],
+                attrs.unique,
+                attrs.sparse,
+                None,
+                expire_after,
+            ));
+        }
+    }
+
+    if !text_fields.is_empty() {
+        let keys = text_fields
+            .into_iter()
+            .map(|name| (name, quote! { "text" }))
+            .collect::<Vec<_>>();
+        indexes.push(expand_index(&keys, false, false, None, None));
+    }
+
+    for index in mongo.indexes.iter() {
+        let keys = index
+            .keys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                let (key, order) = match key.strip_prefix('-') {
+                    Some(key) => (key.trim(), quote! { -1_i32 }),
+                    None => (key, quote! { 1_i32 }),
+                };
+                // the first segment of a dotted path is the field, the rest is a path within it
+                let (field_name, rest) = match key.split_once('.') {
+                    Some((field_name, rest)) => (field_name, Some(rest)),
+                    None => (key, None),
+                };
+                let name = fields
+                    .iter()
+                    .find(|field| field.ident.as_ref().is_some_and(|ident| ident == field_name))
+                    .and_then(|field| field.stored_name(true))
+                    .unwrap_or_else(|| {
+                        abort!(ident.span(), "Unknown field `{}` in index keys `{}`", field_name, index.keys)
+                    });
+                let name = match rest {
+                    Some(rest) => format!("{}.{}", name, rest),
+                    None => name,
+                };
+                (name, order)
+            })
+            .collect::<Vec<_>>();
+
+        if keys.is_empty() {
+            abort!(ident.span(), "Index must have at least one key");
+        }
+
+        indexes.push(expand_index(&keys, index.unique, index.sparse, index.name.as_deref(), None));
+    }
+
+    if indexes.is_empty() {
+        return None;
+    }
+
+    Some(quote! {
+        fn indexes() -> Vec<musty::mongodb::IndexModel> {
+            vec![#(#indexes),*]
+        }
+    })
+}
+
+/// Expands a single `mongodb::IndexModel`
+fn expand_index(
+    keys: &[(String, proc_macro2::TokenStream)],
+    unique: bool,
+    sparse: bool,
+    name: Option<&str>,
+    expire_after: Option<proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    let keys = keys.iter().map(|(name, value)| quote! { keys.insert(#name, #value); });
+    let unique = unique.then(|| quote! { .unique(true) });
+    let sparse = sparse.then(|| quote! { .sparse(true) });
+    let name = name.map(|name| quote! { .name(#name.to_string()) });
+
+    quote! {
+        musty::mongodb::IndexModel::builder()
+            .keys({
+                let mut keys = musty::bson::Document::new();
+                #(#keys)*
+                keys
+            })
+            .options(
+                musty::mongodb::options::IndexOptions::builder()
+                    #unique
+                    #sparse
+                    #name
+                    #expire_after
+                    .build(),
+            )
+            .build()
+    }
+}
+
+/// Parses a TTL as a number of seconds: a number, optionally suffixed with a unit (s, m, h, d)
+fn parse_ttl(ttl: &str) -> Option<u64> {
+    let ttl = ttl.trim();
+    let (value, multiplier) = match ttl.char_indices().last()? {
+        (i, 's') => (&ttl[..i], 1),
+        (i, 'm') => (&ttl[..i], 60),
+        (i, 'h') => (&ttl[..i], 60 * 60),
+        (i, 'd') => (&ttl[..i], 60 * 60 * 24),
+        _ => (ttl, 1),
+    };
+    value.trim().parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
 pub(crate) fn expand_mongo_fields_impl(meta: &MetaModelDerive) -> proc_macro2::TokenStream {
     let ident = &meta.ident;
+    let (impl_generics, ty_generics, where_clause) = meta.generics.split_for_impl();
 
     let fields = match &meta.data {
         darling::ast::Data::Struct(fields) => fields,
@@ -67,13 +366,20 @@ pub(crate) fn expand_mongo_fields_impl(meta: &MetaModelDerive) -> proc_macro2::T
                     field_ident = Ident::new(rename, field_ident.span());
                 }
 
-                let field_name = field_ident.to_string();
+                let field_name = match field.stored_name(true) {
+                    Some(field_name) => field_name,
+                    None => abort!(
+                        field_ident.span(),
+                        "Skipped field `{}` can not be queried",
+                        field_ident
+                    ),
+                };
 
                 let get_by_field_name = format_ident!("get_by_{}", field_ident);
 
                 let func = quote! {
                     pub async fn #get_by_field_name(db: &musty::prelude::Musty<musty::mongodb::Database>, #field_ident: #field_type) -> musty::Result<Option<Self>> {
-                        Ok(Self::find_one(db, musty::bson::doc! { #field_name: #field_ident }).await?)
+                        Self::find_one(db, musty::bson::doc! { #field_name: #field_ident }, None).await
                     }
                 };
                 field_impls.push(func);
@@ -83,7 +389,7 @@ pub(crate) fn expand_mongo_fields_impl(meta: &MetaModelDerive) -> proc_macro2::T
 
     if !field_impls.is_empty() {
         quote! {
-            impl #ident {
+            impl #impl_generics #ident #ty_generics #where_clause {
                 #(#field_impls);*
             }
         }
@@ -0,0 +1,121 @@
+use darling::FromMeta;
+use proc_macro2::{Literal, TokenStream};
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{Ident, Lit, Path};
+
+/// Validations on a model field:
+/// #[musty(validate(length(min = 1, max = 64), email, range(min = 0), regex = "^[a-z]+$", custom = "validate_fn"))]
+#[derive(Default, FromMeta)]
+#[darling(default)]
+pub(crate) struct FieldValidation {
+    /// the length of a string (in characters) or collection is within the bounds
+    pub(crate) length: Option<LengthBounds>,
+    /// the string looks like an email address
+    pub(crate) email: bool,
+    /// the number is within the bounds
+    pub(crate) range: Option<RangeBounds>,
+    /// the string matches the regex
+    pub(crate) regex: Option<String>,
+    /// a function `fn(&FieldType) -> Result<(), String>`, returning the error message if the value is invalid
+    pub(crate) custom: Option<Path>,
+}
+
+#[derive(Default, FromMeta)]
+#[darling(default)]
+pub(crate) struct LengthBounds {
+    pub(crate) min: Option<usize>,
+    pub(crate) max: Option<usize>,
+}
+
+#[derive(Default, FromMeta)]
+#[darling(default)]
+pub(crate) struct RangeBounds {
+    pub(crate) min: Option<Bound>,
+    pub(crate) max: Option<Bound>,
+}
+
+/// A numeric bound, written as an integer or a float
+pub(crate) struct Bound(f64);
+
+impl FromMeta for Bound {
+    fn from_value(value: &Lit) -> darling::Result<Self> {
+        let bound = match value {
+            Lit::Int(int) => int.base10_parse::<f64>(),
+            Lit::Float(float) => float.base10_parse::<f64>(),
+            _ => return Err(darling::Error::unexpected_lit_type(value)),
+        };
+        bound.map(Bound).map_err(darling::Error::from)
+    }
+}
+
+impl FieldValidation {
+    /// Expands the checks of a field, adding the message of every failed check to `errors` under the stored name of the field
+    pub(crate) fn expand(&self, field: &Ident, name: &str) -> TokenStream {
+        let mut checks = Vec::new();
+
+        if let Some(length) = self.length.as_ref() {
+            if length.min.is_none() && length.max.is_none() {
+                abort!(field.span(), "`length` needs a `min` or a `max`");
+            }
+            let min = optional(length.min.map(Literal::usize_suffixed));
+            let max = optional(length.max.map(Literal::usize_suffixed));
+            checks.push(quote! { musty::validation::length(&self.#field, #min, #max) });
+        }
+
+        if self.email {
+            checks.push(quote! { musty::validation::email(&self.#field) });
+        }
+
+        if let Some(range) = self.range.as_ref() {
+            if range.min.is_none() && range.max.is_none() {
+                abort!(field.span(), "`range` needs a `min` or a `max`");
+            }
+            let min = optional(
+                range
+                    .min
+                    .as_ref()
+                    .map(|bound| Literal::f64_suffixed(bound.0)),
+            );
+            let max = optional(
+                range
+                    .max
+                    .as_ref()
+                    .map(|bound| Literal::f64_suffixed(bound.0)),
+            );
+            checks.push(quote! { musty::validation::range(&self.#field, #min, #max) });
+        }
+
+        if let Some(pattern) = self.regex.as_ref() {
+            if let Err(err) = regex::Regex::new(pattern) {
+                abort!(field.span(), "Invalid regex `{}`: {}", pattern, err);
+            }
+            checks.push(quote! {
+                {
+                    static REGEX: std::sync::OnceLock<musty::validation::Regex> = std::sync::OnceLock::new();
+                    let regex = REGEX.get_or_init(|| musty::validation::Regex::new(#pattern).unwrap());
+                    musty::validation::regex(&self.#field, regex)
+                }
+            });
+        }
+
+        if let Some(custom) = self.custom.as_ref() {
+            checks.push(quote! { #custom(&self.#field) });
+        }
+
+        quote! {
+            #(
+                if let Err(message) = #checks {
+                    errors.add(#name, message);
+                }
+            )*
+        }
+    }
+}
+
+fn optional(value: Option<Literal>) -> TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
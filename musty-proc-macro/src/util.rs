@@ -38,4 +38,66 @@ pub(crate) mod string {
             }
         }
     }
+
+    /// rename a field or variant name with a serde `rename_all` rule, i.e: ("user_id", "camelCase") -> "userId".
+    /// returns `None` if the rule is unknown
+    pub fn rename_all(name: &str, rule: &str) -> Option<String> {
+        let words = words(name);
+        let join = |separator: &str, upper: bool| {
+            words
+                .iter()
+                .map(|word| match upper {
+                    true => word.to_ascii_uppercase(),
+                    false => word.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+        let pascal_case = words
+            .iter()
+            .map(|word| capitalize(word))
+            .collect::<String>();
+
+        Some(match rule {
+            "lowercase" => name.to_ascii_lowercase(),
+            "UPPERCASE" => name.to_ascii_uppercase(),
+            "PascalCase" => pascal_case,
+            "camelCase" => {
+                let mut chars = pascal_case.chars();
+                chars.next().map_or_else(String::new, |first| {
+                    first.to_ascii_lowercase().to_string() + chars.as_str()
+                })
+            }
+            "snake_case" => join("_", false),
+            "SCREAMING_SNAKE_CASE" => join("_", true),
+            "kebab-case" => join("-", false),
+            "SCREAMING-KEBAB-CASE" => join("-", true),
+            _ => return None,
+        })
+    }
+
+    /// the lowercase words of a snake_case or PascalCase name, i.e: "UserId" -> ["user", "id"]
+    fn words(name: &str) -> Vec<String> {
+        let mut words: Vec<String> = Vec::new();
+        let mut word = String::new();
+        for c in name.chars() {
+            if (c == '_' || c.is_uppercase()) && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            if c != '_' {
+                word.push(c.to_ascii_lowercase());
+            }
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+        words
+    }
+
+    fn capitalize(word: &str) -> String {
+        let mut chars = word.chars();
+        chars.next().map_or_else(String::new, |first| {
+            first.to_ascii_uppercase().to_string() + chars.as_str()
+        })
+    }
 }
@@ -0,0 +1,27 @@
+use musty::prelude::*;
+use serde_json::json;
+
+#[model]
+struct User {
+    id: u32,
+    name: String,
+}
+
+#[tokio::main]
+pub async fn main() -> musty::Result<()> {
+    // The memory backend does not need a database server, which makes it handy for tests
+    let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+    // Insert a user into the collection
+    let mut user = User {
+        id: 1.into(),
+        name: String::from("jonah"),
+    };
+    user.save(&db).await?;
+
+    // Get the user from the collection by name
+    let user = User::find_one(&db, json!({ "name": "jonah" }), None).await?;
+    println!("{:#?}", user);
+
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use bson::{doc, Bson};
+use bson::doc;
 use mongodb::{options::ClientOptions, Client};
 use musty::prelude::*;
 use serde::{Deserialize, Serialize};
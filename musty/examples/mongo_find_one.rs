@@ -1,6 +1,6 @@
 #![allow(unused_variables)]
 
-use bson::{doc, oid::ObjectId};
+use bson::oid::ObjectId;
 use mongodb::{options::ClientOptions, Client};
 use musty::prelude::*;
 
@@ -27,7 +27,7 @@ pub async fn main() -> musty::Result<()> {
     <User as Model>::Id::new();
 
     // Get the user from the collection by name
-    let user = User::find_one(&db, doc! { "name": "jonah" }).await?;
+    let user = User::find_one(&db, filter!(User, name == "jonah"), None).await?;
     println!("{:#?}", user);
 
     let user2 = User::get_by_name(&db, "jonah".to_string()).await?;
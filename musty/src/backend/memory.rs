@@ -0,0 +1,620 @@
+use std::{
+    any::TypeId,
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicI64, Ordering as AtomicOrdering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+    task::Poll,
+};
+
+use async_trait::async_trait;
+use futures::Stream;
+use serde_json::{Number, Value};
+
+use crate::cursor::AfterLoad;
+use crate::filter::{lookup, unmarked, Filter};
+use crate::model::SaveOutcome;
+use crate::query::{scope_filter, DeletedScope, QueryOptions, SortOrder};
+use crate::{db::Db, model::Model, prelude::Context, prelude::MustyCursor};
+use crate::{error::MustyError, id::IdGuard, prelude::Id, Result};
+
+use super::Backend;
+
+/// An in-process backend which stores models in memory.
+///
+/// Every model type gets its own collection, keyed by the model's id (the variants of an enum model share the collection of the enum model).
+/// Queries can filter and sort on the id as `_id`, like with MongoDB.
+/// Models are stored in their serialized form, so a `MemoryBackend` behaves like a database would:
+/// saving a model and loading it again returns a new instance rather than a shared reference.
+///
+/// This is useful for unit tests and prototyping, where running a database server is not desirable.
+/// Cloning a `MemoryBackend` is cheap, and all clones share the same underlying store.
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    collections: Arc<RwLock<HashMap<TypeId, MemoryCollection>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The collection for the model type `M`, created if it does not exist yet
+    pub fn collection<M: Model + 'static>(&self) -> MemoryCollection {
+        let type_id = M::collection_type();
+        if let Some(collection) = self
+            .collections
+            .read()
+            .ok()
+            .and_then(|collections| collections.get(&type_id).cloned())
+        {
+            return collection;
+        }
+
+        let mut collections = self
+            .collections
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        collections.entry(type_id).or_default().clone()
+    }
+}
+
+impl From<MemoryBackend> for Db<MemoryBackend> {
+    fn from(db: MemoryBackend) -> Self {
+        Db::from_backend(db)
+    }
+}
+
+/// A single stored model: its serialized id, and the serialized model itself
+#[derive(Clone)]
+struct MemoryDocument {
+    id: Value,
+    document: Value,
+}
+
+/// The in-memory collection for a single model type.
+/// Documents are keyed by the string representation of the model's id.
+#[derive(Clone, Default)]
+pub struct MemoryCollection {
+    documents: Arc<RwLock<BTreeMap<String, MemoryDocument>>>,
+    /// the last value of the sequence of this collection, see [`Backend::next_sequence`]
+    sequence: Arc<AtomicI64>,
+}
+
+impl MemoryCollection {
+    /// The number of models stored in this collection
+    pub fn len(&self) -> usize {
+        self.read().map(|documents| documents.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, BTreeMap<String, MemoryDocument>>> {
+        self.documents
+            .read()
+            .map_err(|_| MustyError::Other(anyhow::anyhow!("Memory collection lock poisoned")))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, BTreeMap<String, MemoryDocument>>> {
+        self.documents
+            .write()
+            .map_err(|_| MustyError::Other(anyhow::anyhow!("Memory collection lock poisoned")))
+    }
+}
+
+impl MemoryDocument {
+    /// Deserializes the stored document into a model, restoring its id
+    fn to_model<C: Model>(&self) -> Result<C> {
+        let mut model: C = serde_json::from_value(self.document.clone())?;
+        model.set_id(serde_json::from_value(self.id.clone())?);
+        Ok(model)
+    }
+
+    /// The stored model with its id as `_id`, so queries can filter and sort on the id like they do with MongoDB
+    fn with_id(&self) -> Cow<'_, Value> {
+        match &self.document {
+            Value::Object(fields) if !fields.contains_key("_id") => {
+                let mut fields = fields.clone();
+                fields.insert("_id".to_string(), self.id.clone());
+                Cow::Owned(Value::Object(fields))
+            }
+            document => Cow::Borrowed(document),
+        }
+    }
+
+    /// Whether the stored document is a model of type `C`, which is not the case for the other variants of an enum model
+    fn is_variant<C: Model>(&self) -> bool {
+        scope_filter::<C>(DeletedScope::Include)
+            .iter()
+            .all(|scope| matches_filter(scope, &self.document))
+    }
+}
+
+/// The filter type used by [`MemoryBackend`].
+///
+/// A filter is a predicate over the serialized (JSON) form of a model.
+/// A JSON object can be converted into a filter, which matches models whose fields are equal to every field of the object
+/// (ex: `serde_json::json!({ "name": "John" })`).
+#[derive(Clone)]
+pub struct MemoryFilter {
+    predicate: Arc<dyn Fn(&Value) -> bool + Send + Sync>,
+    /// the serialization error of an invalid [`Filter`], returned by the operations this filter is passed to
+    error: Option<String>,
+}
+
+impl MemoryFilter {
+    /// Creates a filter from a predicate over the serialized model
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&Value) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            predicate: Arc::new(predicate),
+            error: None,
+        }
+    }
+
+    /// A filter that matches every model
+    pub fn all() -> Self {
+        Self::new(|_| true)
+    }
+
+    /// Whether the given serialized model matches this filter
+    pub fn matches(&self, document: &Value) -> bool {
+        (self.predicate)(document)
+    }
+}
+
+impl Default for MemoryFilter {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl From<Value> for MemoryFilter {
+    fn from(filter: Value) -> Self {
+        Self::new(move |document| match &filter {
+            Value::Object(fields) => fields
+                .iter()
+                .all(|(field, value)| document.get(field) == Some(value)),
+            Value::Null => true,
+            other => document == other,
+        })
+    }
+}
+
+/// Evaluates a database-agnostic [`Filter`] against the serialized model, following MongoDB's semantics where practical:
+/// `Ne` matches missing fields, and equality against an array field matches any of its elements.
+impl From<Filter> for MemoryFilter {
+    fn from(filter: Filter) -> Self {
+        let error = filter.error().map(str::to_string);
+        Self {
+            error,
+            ..Self::new(move |document| matches_filter(&filter, document))
+        }
+    }
+}
+
+fn matches_filter(filter: &Filter, document: &Value) -> bool {
+    match filter {
+        Filter::Eq(field, value) => field_equals(lookup(document, field), value),
+        Filter::Ne(field, value) => !field_equals(lookup(document, field), value),
+        Filter::Gt(field, value) => {
+            lookup(document, field).and_then(|field| compare(field, value))
+                == Some(Ordering::Greater)
+        }
+        Filter::Lt(field, value) => {
+            lookup(document, field).and_then(|field| compare(field, value)) == Some(Ordering::Less)
+        }
+        Filter::In(field, values) => {
+            let field = lookup(document, field);
+            values.iter().any(|value| field_equals(field, value))
+        }
+        Filter::Exists(field, exists) => lookup(document, field).is_some() == *exists,
+        Filter::And(filters) => filters
+            .iter()
+            .all(|filter| matches_filter(filter, document)),
+        Filter::Or(filters) => filters
+            .iter()
+            .any(|filter| matches_filter(filter, document)),
+        Filter::Not(filter) => !matches_filter(filter, document),
+        Filter::Invalid(_) => false,
+    }
+}
+
+fn field_equals(field: Option<&Value>, value: &Value) -> bool {
+    match field {
+        Some(Value::Array(elements)) if !value.is_array() => elements
+            .iter()
+            .any(|element| compare(element, value) == Some(Ordering::Equal)),
+        Some(field) => compare(field, value) == Some(Ordering::Equal),
+        None => value.is_null(),
+    }
+}
+
+/// Compares two values of the same kind, numbers are compared regardless of their representation
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (unmarked(a), unmarked(b)) {
+        (Value::Number(a), Value::Number(b)) => compare_numbers(a, b),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (a, b) if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+/// Integers are compared exactly, since integers above 2^53 (ex: snowflake ids) can not all be told apart as `f64`s.
+/// Floats are compared as `f64`s
+fn compare_numbers(a: &Number, b: &Number) -> Option<Ordering> {
+    let integer = |n: &Number| {
+        n.as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from))
+    };
+    match (integer(a), integer(b)) {
+        (Some(a), Some(b)) => Some(a.cmp(&b)),
+        _ => a.as_f64()?.partial_cmp(&b.as_f64()?),
+    }
+}
+
+/// A stream of models found in a [`MemoryBackend`].
+/// The models are loaded when the cursor is created, so this stream only waits for the `after_load` hooks of the models, if any.
+pub struct MemoryCursor<M>
+where
+    M: Model,
+{
+    models: std::vec::IntoIter<Result<M>>,
+    after_load: Option<AfterLoad<MemoryBackend, M>>,
+}
+
+impl<M> Unpin for MemoryCursor<M> where M: Model {}
+
+impl<M> MustyCursor<M> for MemoryCursor<M> where M: Model + 'static {}
+
+impl<M> Stream for MemoryCursor<M>
+where
+    M: Model + 'static,
+{
+    type Item = Result<M>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let models = &mut this.models;
+        match this.after_load.as_mut() {
+            Some(after_load) => after_load.poll_next(cx, |_| Poll::Ready(models.next())),
+            None => Poll::Ready(models.next()),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.models.size_hint()
+    }
+}
+
+/// Applies a filter and query options to the stored documents, in that order: filter (and the variant & soft delete scope), sort, skip, limit, projection.
+/// Without sort keys, documents are returned in the order of their ids' string representation.
+/// Fails with [`MustyError::InvalidFilter`] if the filter is invalid
+fn query<'a, C: Model>(
+    documents: impl Iterator<Item = &'a MemoryDocument>,
+    filter: &MemoryFilter,
+    options: &QueryOptions,
+) -> Result<Vec<MemoryDocument>> {
+    if let Some(err) = &filter.error {
+        return Err(MustyError::InvalidFilter(err.clone()));
+    }
+    let scope = scope_filter::<C>(options.deleted);
+    let mut matching: Vec<(Cow<'_, Value>, &MemoryDocument)> = documents
+        .map(|stored| (stored.with_id(), stored))
+        .filter(|(document, _)| filter.matches(document))
+        .filter(|(document, _)| scope.iter().all(|scope| matches_filter(scope, document)))
+        .collect();
+
+    if !options.sort.is_empty() {
+        matching.sort_by(|(a, _), (b, _)| {
+            options
+                .sort
+                .iter()
+                .map(|sort| {
+                    let ordering = compare_for_sort(lookup(a, &sort.field), lookup(b, &sort.field));
+                    match sort.order {
+                        SortOrder::Ascending => ordering,
+                        SortOrder::Descending => ordering.reverse(),
+                    }
+                })
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    let skip = options.skip.unwrap_or(0) as usize;
+    let limit = options.limit.map_or(usize::MAX, |limit| limit as usize);
+    let projection = options.projection.clone();
+
+    Ok(matching
+        .into_iter()
+        .skip(skip)
+        .take(limit)
+        .map(move |(_, stored)| match &projection {
+            Some(fields) => MemoryDocument {
+                id: stored.id.clone(),
+                document: project(&stored.document, fields),
+            },
+            None => stored.clone(),
+        })
+        .collect())
+}
+
+/// Sorts missing and null values first, like MongoDB does. Values of different kinds are considered equal
+fn compare_for_sort(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a.filter(|a| !a.is_null()), b.filter(|b| !b.is_null())) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Less,
+        (Some(_), None) => Ordering::Greater,
+        (Some(a), Some(b)) => compare(a, b).unwrap_or(Ordering::Equal),
+    }
+}
+
+/// Copies only the given (possibly dotted) field paths of a serialized model
+fn project(document: &Value, fields: &[String]) -> Value {
+    let mut projected = Value::Object(Default::default());
+    for field in fields {
+        if let Some(value) = lookup(document, field) {
+            let mut target = &mut projected;
+            for segment in field.split('.') {
+                target = target
+                    .as_object_mut()
+                    .expect("projected values are objects")
+                    .entry(segment)
+                    .or_insert_with(|| Value::Object(Default::default()));
+            }
+            *target = value.clone();
+        }
+    }
+    projected
+}
+
+#[async_trait]
+impl Backend for MemoryBackend {
+    type Filter = MemoryFilter;
+    type Cursor<C: Model + 'static> = MemoryCursor<C>;
+
+    async fn get_model_by_id<C, I>(&self, id: &Id<C, I>) -> Result<Option<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let key = match &id.inner {
+            Some(id) => id.to_string(),
+            None => return Ok(None),
+        };
+
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let documents = collection.read()?;
+        documents
+            .get(&key)
+            .filter(|stored| stored.is_variant::<C>())
+            .map(MemoryDocument::to_model)
+            .transpose()
+    }
+
+    async fn get_models_by_ids<C, I>(&self, ids: &[Id<C, I>]) -> Result<Vec<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let keys = ids
+            .iter()
+            .filter_map(|id| id.inner.as_ref().map(ToString::to_string))
+            .collect::<BTreeSet<_>>();
+
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let documents = collection.read()?;
+        keys.iter()
+            .filter_map(|key| documents.get(key))
+            .filter(|stored| stored.is_variant::<C>())
+            .map(MemoryDocument::to_model)
+            .collect()
+    }
+
+    /// Save this model instance to the in-memory collection, replacing any model with the same id.
+    /// Saving a variant with the id of a model of another variant fails with [`MustyError::IdConflict`]
+    async fn save_model<C, I>(&self, model: &mut C) -> Result<SaveOutcome<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let key = match &model.id().inner {
+            Some(id) => id.to_string(),
+            None => return Err(MustyError::ModelIdRequiredForOperation),
+        };
+
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let mut documents = collection.write()?;
+        if documents
+            .get(&key)
+            .is_some_and(|previous| !previous.is_variant::<C>())
+        {
+            return Err(MustyError::IdConflict);
+        }
+
+        // versioned models are only saved if the stored document still has the version the model was loaded with
+        let version = match (C::VERSION_FIELD, model.version()?) {
+            (Some(field), Some(version)) => {
+                let expected = MemoryFilter::from(Filter::eq(field, version));
+                if let Some(previous) = documents.get(&key) {
+                    if !expected.matches(&previous.document) {
+                        return Err(MustyError::VersionConflict);
+                    }
+                }
+                let next = version
+                    .checked_add(1)
+                    .ok_or(MustyError::VersionOutOfRange)?;
+                model.set_version(next)?;
+                Some(version)
+            }
+            _ => None,
+        };
+
+        let document = match (
+            serde_json::to_value(model.id()),
+            serde_json::to_value(&*model),
+        ) {
+            (Ok(id), Ok(document)) => MemoryDocument { id, document },
+            (Err(err), _) | (_, Err(err)) => {
+                if let Some(version) = version {
+                    model.set_version(version)?;
+                }
+                return Err(err.into());
+            }
+        };
+
+        match documents.insert(key, document.clone()) {
+            None => Ok(SaveOutcome::Inserted {
+                id: model.id().clone(),
+            }),
+            Some(previous) if previous.document == document.document => Ok(SaveOutcome::Unchanged),
+            Some(_) => Ok(SaveOutcome::Updated),
+        }
+    }
+
+    async fn next_sequence<C, I>(&self) -> Result<i64>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        Ok(collection.sequence.fetch_add(1, AtomicOrdering::SeqCst) + 1)
+    }
+
+    async fn delete_model<C, I>(&self, model: &mut C) -> Result<bool>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let key = match &model.id().inner {
+            Some(id) => id.to_string(),
+            None => return Err(MustyError::ModelIdRequiredForOperation),
+        };
+
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let mut documents = collection.write()?;
+        if !documents
+            .get(&key)
+            .is_some_and(|stored| stored.is_variant::<C>())
+        {
+            return Ok(false);
+        }
+        Ok(documents.remove(&key).is_some())
+    }
+
+    async fn set_model_deleted_at<C, I>(&self, model: &C) -> Result<bool>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let (key, field) = match (&model.id().inner, C::SOFT_DELETE_FIELD) {
+            (Some(id), Some(field)) => (id.to_string(), field),
+            (None, _) => return Err(MustyError::ModelIdRequiredForOperation),
+            (_, None) => return Ok(false),
+        };
+        let marker = lookup(&serde_json::to_value(model)?, field)
+            .cloned()
+            .unwrap_or(Value::Null);
+
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let mut documents = collection.write()?;
+        match documents
+            .get_mut(&key)
+            .filter(|stored| stored.is_variant::<C>())
+            .and_then(|stored| stored.document.as_object_mut())
+        {
+            Some(document) => {
+                document.insert(field.to_string(), marker);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn find_one<C, I, F>(&self, filter: F, options: Option<QueryOptions>) -> Result<Option<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync,
+    {
+        let options = options.unwrap_or_default().limit(1);
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let documents = collection.read()?;
+        query::<C>(documents.values(), &filter.into(), &options)?
+            .first()
+            .map(MemoryDocument::to_model)
+            .transpose()
+    }
+
+    async fn find_many<C, I, F>(
+        &self,
+        filter: F,
+        options: Option<QueryOptions>,
+    ) -> Result<Self::Cursor<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync,
+    {
+        let options = options.unwrap_or_default();
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let documents = collection.read()?;
+        let models: Vec<Result<C>> = query::<C>(documents.values(), &filter.into(), &options)?
+            .iter()
+            .map(MemoryDocument::to_model)
+            .collect();
+
+        Ok(MemoryCursor {
+            models: models.into_iter(),
+            after_load: None,
+        })
+    }
+
+    async fn count_models<C, I, F>(&self, filter: F, options: Option<QueryOptions>) -> Result<u64>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync,
+    {
+        let options = options.unwrap_or_default();
+        let collection = C::contextualize_boxed_downcast::<MemoryCollection>(self)?;
+        let documents = collection.read()?;
+        Ok(query::<C>(documents.values(), &filter.into(), &options)?.len() as u64)
+    }
+
+    fn with_hooks<C>(cursor: Self::Cursor<C>, db: Db<Self>) -> Self::Cursor<C>
+    where
+        C: Model + 'static,
+    {
+        MemoryCursor {
+            after_load: Some(AfterLoad::new(db)),
+            ..cursor
+        }
+    }
+}
+
+impl<I, M> Context<I, MemoryBackend> for M
+where
+    M: Model + 'static,
+    I: IdGuard,
+{
+    type Output = MemoryCollection;
+
+    fn contextualize(db: &MemoryBackend) -> Self::Output {
+        db.collection::<M>()
+    }
+}
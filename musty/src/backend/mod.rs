@@ -1,16 +1,27 @@
 #[cfg(feature = "mongodb")]
 mod mongo;
 
+#[cfg(feature = "memory")]
+#[cfg_attr(docsrs, doc(cfg(feature = "memory")))]
+pub mod memory;
+
 /// Exposes basic database-agnostic model operations.
 #[async_trait]
-pub trait Backend: Send + Sync + Sized {
+pub trait Backend: Clone + Send + Sync + Sized + 'static {
     type Filter: Send + Sync;
+    type Cursor<C: Model + 'static>: MustyCursor<C> + Send;
 
     async fn get_model_by_id<C, I>(&self, id: &Id<C, I>) -> Result<Option<C>>
     where
         I: IdGuard,
         C: Context<I, Self> + Model + 'static;
-    async fn save_model<C, I>(&self, model: &mut C) -> Result<bool>
+    /// Get every model with one of the given ids in a single query, in no particular order.
+    /// Ids which are not set or not found are ignored
+    async fn get_models_by_ids<C, I>(&self, ids: &[Id<C, I>]) -> Result<Vec<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static;
+    async fn save_model<C, I>(&self, model: &mut C) -> Result<SaveOutcome<C>>
     where
         I: IdGuard,
         C: Context<I, Self> + Model + 'static;
@@ -18,18 +29,84 @@ pub trait Backend: Send + Sync + Sized {
     where
         I: IdGuard,
         C: Context<I, Self> + Model + 'static;
+    /// Set the soft delete marker of the stored model to the marker of `model` (see [`Model::SOFT_DELETE_FIELD`]),
+    /// leaving its other stored fields as they are. Nothing is inserted if no model is stored with its id, in which case this returns `false`.
+    /// Backends which do not support soft delete keep the default implementation, which fails
+    async fn set_model_deleted_at<C, I>(&self, _model: &C) -> Result<bool>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        Err(MustyError::Other(anyhow::anyhow!(
+            "Could not soft delete model: not supported by this backend"
+        )))
+    }
+    /// Increment the sequence of the collection of `C` and get its new value, starting at 1.
+    /// The increment is atomic, so concurrent calls never get the same value.
+    /// Backends which do not support the `sequence` id strategy keep the default implementation, which fails
+    async fn next_sequence<C, I>(&self) -> Result<i64>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        Err(MustyError::Other(anyhow::anyhow!(
+            "Could not generate sequence id: not supported by this backend"
+        )))
+    }
 
-    async fn find_one<C, I, F>(&self, filter: F) -> Result<Option<C>>
+    async fn find_one<C, I, F>(
+        &self,
+        filter: F,
+        options: Option<QueryOptions>,
+    ) -> Result<Option<C>>
     where
         I: IdGuard,
         C: Context<I, Self> + Model + 'static,
         F: Into<Self::Filter> + Send + Sync;
+
+    async fn find_many<C, I, F>(
+        &self,
+        filter: F,
+        options: Option<QueryOptions>,
+    ) -> Result<Self::Cursor<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync;
+
+    /// Count the models matching a filter, in the soft delete scope of the options.
+    /// The skip and limit of the options are applied to the count, the sort and projection are ignored.
+    /// The default implementation loads the models with [`Backend::find_many`], backends should count them without loading them
+    async fn count_models<C, I, F>(&self, filter: F, options: Option<QueryOptions>) -> Result<u64>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync,
+    {
+        self.find_many::<C, I, F>(filter, options)
+            .await?
+            .try_fold(0, |count, _| async move { Ok(count + 1) })
+            .await
+    }
+
+    /// Runs the `after_load` hook of every model loaded by the cursor, see [`ModelHooks`](crate::prelude::ModelHooks).
+    /// The default implementation returns the cursor as is, so the hook does not run on the models found by backends which keep it
+    fn with_hooks<C>(cursor: Self::Cursor<C>, _db: Db<Self>) -> Self::Cursor<C>
+    where
+        C: Model + 'static,
+    {
+        cursor
+    }
 }
 
 #[cfg(feature = "mongodb")]
-pub use mongo::MongoModel;
+pub use mongo::{
+    Aggregate, AggregateCursor, ChangeEvent, GroupId, IndexDrift, IndexRegistration,
+    IndexSyncOptions, IndexSyncReport, MongoChangeStream, MongoCursor, MongoFilter, MongoModel,
+};
 
 use async_trait::async_trait;
+use futures::TryStreamExt;
 
-use crate::prelude::{Context, Id, IdGuard, Model};
-use crate::Result;
+use crate::prelude::{Context, Id, IdGuard, Model, MustyCursor, QueryOptions, SaveOutcome};
+use crate::{db::Db, error::MustyError, Result};
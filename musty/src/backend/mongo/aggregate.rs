@@ -0,0 +1,292 @@
+use std::{marker::PhantomData, pin::Pin, task::Poll};
+
+use bson::{Bson, Document};
+use futures::Stream;
+use mongodb::{options::AggregateOptions, Database};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    db::Db,
+    error::MustyError,
+    field::Field,
+    query::{scope_filter, DeletedScope, Sort, SortOrder},
+    Model, Result,
+};
+
+use super::{MongoFilter, MongoModel};
+
+/// An aggregation pipeline on the collection of a model, see [`MongoModel::aggregate`].
+///
+/// Stages are added in order, and field paths can be typed fields of the models (ex: `Post::fields().author`):
+/// ```ignore
+/// #[derive(Deserialize)]
+/// struct PostCount {
+///     #[serde(rename = "_id")]
+///     author: String,
+///     posts: i64,
+/// }
+///
+/// let counts: Vec<PostCount> = Post::aggregate(&db)
+///     .match_(filter!(Post, published == true))
+///     .group(Post::fields().author, doc! { "posts": { "$sum": 1 } })
+///     .sort(Sort::desc("posts"))
+///     .limit(10)
+///     .into_stream()
+///     .await?
+///     .try_collect()
+///     .await?;
+/// ```
+/// Soft-deleted models are left out, and the variants of an enum model only aggregate the models of their variant,
+/// like with [`Model::find_many`](crate::Model::find_many)
+pub struct Aggregate<M: MongoModel> {
+    db: Db<Database>,
+    stages: Vec<Document>,
+    /// The error of the first filter which could not be translated, returned when running the pipeline
+    error: Option<String>,
+    deleted: DeletedScope,
+    options: Option<AggregateOptions>,
+    model: PhantomData<M>,
+}
+
+impl<M: MongoModel> Aggregate<M> {
+    pub fn new(db: &Db<Database>) -> Self {
+        Self {
+            db: db.clone(),
+            stages: Vec::new(),
+            error: None,
+            deleted: DeletedScope::default(),
+            options: None,
+            model: PhantomData,
+        }
+    }
+
+    /// Keeps the documents matching the filter (ex: `filter!(Post, published == true)`), with `$match`.
+    /// A filter which can not be translated fails the aggregation with [`MustyError::InvalidFilter`]
+    pub fn match_(mut self, filter: impl Into<MongoFilter>) -> Self {
+        match filter.into().into_document() {
+            Ok(filter) => self.stage(bson::doc! { "$match": filter }),
+            Err(MustyError::InvalidFilter(err)) => {
+                self.error.get_or_insert(err);
+                self
+            }
+            Err(err) => {
+                self.error.get_or_insert(err.to_string());
+                self
+            }
+        }
+    }
+
+    /// Groups the documents by the given field (or `Bson::Null` for a single group of all the documents), with `$group`.
+    /// The accumulators compute the other fields of the groups (ex: `doc! { "total": { "$sum": "$amount" } }`)
+    pub fn group(self, id: impl Into<GroupId>, accumulators: Document) -> Self {
+        let mut group = bson::doc! { "_id": id.into().0 };
+        group.extend(accumulators);
+        self.stage(bson::doc! { "$group": group })
+    }
+
+    /// Sorts the documents by the given key, after the keys of the previous stage if it is also a sort, with `$sort`
+    pub fn sort(mut self, sort: impl Into<Sort>) -> Self {
+        let sort = sort.into();
+        let order = match sort.order {
+            SortOrder::Ascending => 1,
+            SortOrder::Descending => -1,
+        };
+        if let Some(Ok(keys)) = self
+            .stages
+            .last_mut()
+            .map(|stage| stage.get_document_mut("$sort"))
+        {
+            keys.insert(sort.field, order);
+            return self;
+        }
+        let mut keys = Document::new();
+        keys.insert(sort.field, order);
+        self.stage(bson::doc! { "$sort": keys })
+    }
+
+    /// Only keeps the given fields of the documents (and their `_id`), with `$project`
+    pub fn project<F: Into<String>>(self, fields: impl IntoIterator<Item = F>) -> Self {
+        let fields = fields
+            .into_iter()
+            .map(|field| (field.into(), Bson::Int32(1)))
+            .collect::<Document>();
+        self.stage(bson::doc! { "$project": fields })
+    }
+
+    /// Joins the models of type `F` whose `foreign_field` equals the `local_field` of the documents,
+    /// as an array in the `as_` field of the documents, with `$lookup`
+    pub fn lookup<F: MongoModel>(
+        self,
+        local_field: impl Into<String>,
+        foreign_field: impl Into<String>,
+        as_: impl Into<String>,
+    ) -> Self {
+        self.stage(bson::doc! {
+            "$lookup": {
+                "from": F::COLLECTION_NAME,
+                "localField": local_field.into(),
+                "foreignField": foreign_field.into(),
+                "as": as_.into(),
+            }
+        })
+    }
+
+    /// Outputs a document for each element of the array in the given field, with `$unwind`
+    pub fn unwind(self, field: impl Into<String>) -> Self {
+        self.stage(bson::doc! { "$unwind": path(field.into()) })
+    }
+
+    /// Only keeps the first `limit` documents, with `$limit`
+    pub fn limit(self, limit: u64) -> Self {
+        self.stage(bson::doc! { "$limit": limit as i64 })
+    }
+
+    /// Runs a sub-pipeline on the documents, outputting its results as an array in the `name` field, with `$facet`.
+    /// Facets added one after the other run in the same `$facet` stage, and output a single document:
+    /// ```ignore
+    /// Post::aggregate(&db)
+    ///     .facet("authors", |posts| posts.group(Post::fields().author, doc! {}))
+    ///     .facet("latest", |posts| posts.sort(Post::fields().created_at.desc()).limit(5))
+    /// ```
+    pub fn facet(mut self, name: impl Into<String>, facet: impl FnOnce(Self) -> Self) -> Self {
+        let facet = facet(Self::new(&self.db));
+        if let Some(err) = facet.error {
+            self.error.get_or_insert(err);
+        }
+        let pipeline = facet.stages;
+        if let Some(Ok(facets)) = self
+            .stages
+            .last_mut()
+            .map(|stage| stage.get_document_mut("$facet"))
+        {
+            facets.insert(name.into(), pipeline);
+            return self;
+        }
+        let mut facets = Document::new();
+        facets.insert(name.into(), pipeline);
+        self.stage(bson::doc! { "$facet": facets })
+    }
+
+    /// Adds any other stage (ex: `doc! { "$count": "total" }`)
+    pub fn stage(mut self, stage: Document) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Also aggregates soft-deleted models
+    pub fn with_deleted(mut self) -> Self {
+        self.deleted = DeletedScope::Include;
+        self
+    }
+
+    /// Only aggregates soft-deleted models
+    pub fn only_deleted(mut self) -> Self {
+        self.deleted = DeletedScope::Only;
+        self
+    }
+
+    /// Sets the options of the aggregation (ex: `allow_disk_use`)
+    pub fn options(mut self, options: AggregateOptions) -> Self {
+        self.options = Some(options);
+        self
+    }
+
+    /// The stages of the pipeline, starting with the `$match` stage restricting it to the models in scope.
+    /// Fails with [`MustyError::InvalidFilter`] if one of the filters could not be translated
+    pub fn pipeline(&self) -> Result<Vec<Document>> {
+        if let Some(err) = &self.error {
+            return Err(MustyError::InvalidFilter(err.clone()));
+        }
+        let scope = scope_filter::<M>(self.deleted)
+            .map(|scope| Document::try_from(scope).map(|scope| bson::doc! { "$match": scope }))
+            .transpose()?;
+        Ok(scope
+            .into_iter()
+            .chain(self.stages.iter().cloned())
+            .collect())
+    }
+
+    /// Runs the aggregation, and deserializes its results into `T`
+    pub async fn into_stream<T>(self) -> Result<AggregateCursor<T>>
+    where
+        T: DeserializeOwned + Unpin + Send + Sync,
+    {
+        let cursor = M::collection(&self.db)
+            .aggregate(self.pipeline()?, self.options)
+            .await?;
+        Ok(AggregateCursor::new(cursor.with_type()))
+    }
+}
+
+/// The `_id` of the groups of a `$group` stage: a field path, or any other expression
+pub struct GroupId(Bson);
+
+impl From<String> for GroupId {
+    fn from(field: String) -> Self {
+        Self(Bson::String(path(field)))
+    }
+}
+
+impl From<&str> for GroupId {
+    fn from(field: &str) -> Self {
+        field.to_string().into()
+    }
+}
+
+impl<M: Model, T> From<Field<M, T>> for GroupId {
+    fn from(field: Field<M, T>) -> Self {
+        String::from(field).into()
+    }
+}
+
+impl From<Bson> for GroupId {
+    fn from(id: Bson) -> Self {
+        Self(id)
+    }
+}
+
+impl From<Document> for GroupId {
+    fn from(id: Document) -> Self {
+        Self(Bson::Document(id))
+    }
+}
+
+/// A field path in an expression, prefixed with `$`
+fn path(field: String) -> String {
+    match field.starts_with('$') {
+        true => field,
+        false => format!("${field}"),
+    }
+}
+
+/// The results of an aggregation, deserialized into `T`, see [`Aggregate::into_stream`]
+pub struct AggregateCursor<T> {
+    cursor: mongodb::Cursor<T>,
+}
+
+impl<T> Unpin for AggregateCursor<T> {}
+
+impl<T> AggregateCursor<T> {
+    pub fn new(cursor: mongodb::Cursor<T>) -> Self {
+        Self { cursor }
+    }
+}
+
+impl<T> Stream for AggregateCursor<T>
+where
+    T: DeserializeOwned + Unpin + Send + Sync,
+{
+    type Item = Result<T>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        match Pin::new(&mut self.cursor).poll_next(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(MustyError::from(err)))),
+            Poll::Ready(Some(Ok(result))) => Poll::Ready(Some(Ok(result))),
+        }
+    }
+}
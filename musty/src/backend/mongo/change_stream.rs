@@ -0,0 +1,134 @@
+use std::{pin::Pin, task::Poll};
+
+use bson::{Bson, Document};
+use futures::Stream;
+use mongodb::change_stream::{
+    event::{ChangeStreamEvent, OperationType, ResumeToken},
+    ChangeStream,
+};
+
+use crate::{error::MustyError, id::Id, model::Model, Result};
+
+/// A change made to a model in MongoDB, see [`MongoModel::watch`](crate::MongoModel::watch)
+#[derive(Debug)]
+pub enum ChangeEvent<M: Model> {
+    /// The model was inserted
+    Insert(M),
+    /// The model was updated
+    Update {
+        id: Id<M, M::Id>,
+        /// The new values of the updated fields, by their name in the database
+        updated_fields: Document,
+        /// The names of the removed fields
+        removed_fields: Vec<String>,
+        /// The model after the update, if the change stream looks it up
+        /// (with `FullDocumentType::UpdateLookup` in the options of the change stream)
+        model: Option<M>,
+    },
+    /// The model was replaced, by saving it for example
+    Replace(M),
+    /// The model was deleted
+    Delete(Id<M, M::Id>),
+}
+
+impl<M: Model> ChangeEvent<M> {
+    /// The id of the changed model
+    pub fn id(&self) -> &Id<M, M::Id> {
+        match self {
+            Self::Insert(model) | Self::Replace(model) => model.id(),
+            Self::Update { id, .. } | Self::Delete(id) => id,
+        }
+    }
+
+    /// The change of a model from a change stream event, `None` for changes to the collection itself (ex: drops)
+    fn from_event(event: ChangeStreamEvent<M>) -> Result<Option<Self>> {
+        let id = event
+            .document_key
+            .and_then(|mut key| key.remove("_id"))
+            .unwrap_or(Bson::Null);
+        let model = event.full_document;
+        let missing = || {
+            MustyError::Other(anyhow::anyhow!(
+                "MongoDB change event is missing the changed document"
+            ))
+        };
+
+        Ok(Some(match event.operation_type {
+            OperationType::Insert => Self::Insert(model.ok_or_else(missing)?),
+            OperationType::Replace => Self::Replace(model.ok_or_else(missing)?),
+            OperationType::Delete => Self::Delete(bson::from_bson(id)?),
+            OperationType::Update => {
+                let (updated_fields, removed_fields) = event
+                    .update_description
+                    .map(|update| (update.updated_fields, update.removed_fields))
+                    .unwrap_or_default();
+                Self::Update {
+                    id: bson::from_bson(id)?,
+                    updated_fields,
+                    removed_fields,
+                    model,
+                }
+            }
+            _ => return Ok(None),
+        }))
+    }
+}
+
+/// A stream of the changes made to models in MongoDB, wrapping a `mongodb::change_stream::ChangeStream`.
+///
+/// Changes to the collection itself (drops, renames and invalidations) end the stream rather than being streamed.
+/// The stream can be restarted where it stopped (ex: after a crash) from its [resume token](MongoChangeStream::resume_token),
+/// with `ChangeStreamOptions::builder().start_after(token)`
+pub struct MongoChangeStream<M>
+where
+    M: Model,
+{
+    stream: ChangeStream<ChangeStreamEvent<M>>,
+}
+
+impl<M> Unpin for MongoChangeStream<M> where M: Model {}
+
+impl<M> MongoChangeStream<M>
+where
+    M: Model,
+{
+    pub fn new(stream: ChangeStream<ChangeStreamEvent<M>>) -> Self {
+        Self { stream }
+    }
+
+    /// The resume token of the last change returned by this stream (or of the start of the stream, if no change has been returned yet).
+    /// It can be stored to restart the stream after this change, see [`MongoChangeStream`]
+    pub fn resume_token(&self) -> Option<ResumeToken> {
+        self.stream.resume_token()
+    }
+
+    /// Whether the stream can return more changes
+    pub fn is_alive(&self) -> bool {
+        self.stream.is_alive()
+    }
+}
+
+impl<M> Stream for MongoChangeStream<M>
+where
+    M: Model,
+{
+    type Item = Result<ChangeEvent<M>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            return match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Pending => Poll::Pending,
+                Poll::Ready(None) => Poll::Ready(None),
+                Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(MustyError::from(err)))),
+                Poll::Ready(Some(Ok(event))) => match ChangeEvent::from_event(event) {
+                    Ok(Some(change)) => Poll::Ready(Some(Ok(change))),
+                    Ok(None) => continue,
+                    Err(err) => Poll::Ready(Some(Err(err))),
+                },
+            };
+        }
+    }
+}
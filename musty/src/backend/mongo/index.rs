@@ -0,0 +1,315 @@
+use bson::{Bson, Document};
+use futures::{future::BoxFuture, TryStreamExt};
+use mongodb::{error::ErrorKind, Database, IndexModel};
+
+use crate::{db::Db, Result};
+
+use super::MongoModel;
+
+/// Options for [`Musty::sync_indexes`](crate::Musty::sync_indexes) and [`Musty::sync_all_indexes`](crate::Musty::sync_all_indexes)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexSyncOptions {
+    /// Drop the indexes of the collection which are not declared on the model (the `_id` index is never dropped)
+    pub drop_undeclared: bool,
+}
+
+impl IndexSyncOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops the indexes of the collection which are not declared on the model
+    pub fn drop_undeclared(mut self, drop_undeclared: bool) -> Self {
+        self.drop_undeclared = drop_undeclared;
+        self
+    }
+}
+
+/// The result of syncing the declared indexes of a model with its collection
+#[derive(Clone, Debug, Default)]
+pub struct IndexSyncReport {
+    /// The name of the collection
+    pub collection: String,
+    /// The names of the declared indexes which were missing, and have been created
+    pub created: Vec<String>,
+    /// The declared indexes which exist with the same keys, but different options.
+    /// These are not changed: the existing index has to be dropped for the declared one to be created
+    pub drifted: Vec<IndexDrift>,
+    /// The names of the existing indexes which are not declared on the model
+    pub undeclared: Vec<String>,
+    /// The names of the undeclared indexes which have been dropped, see [`IndexSyncOptions::drop_undeclared`]
+    pub dropped: Vec<String>,
+}
+
+impl IndexSyncReport {
+    /// Whether the collection already had exactly the declared indexes
+    pub fn is_in_sync(&self) -> bool {
+        self.created.is_empty() && self.drifted.is_empty() && self.undeclared.is_empty()
+    }
+}
+
+/// A declared index which differs from the existing index with the same keys
+#[derive(Clone, Debug)]
+pub struct IndexDrift {
+    /// The name of the existing index
+    pub name: String,
+    pub declared: IndexModel,
+    pub existing: IndexModel,
+}
+
+impl Db<Database> {
+    /// Syncs the indexes declared on the model (see [`MongoModel::indexes`]) with its collection.
+    ///
+    /// Missing indexes are created, and indexes which exist with different options are reported as drifted.
+    /// Indexes which are no longer declared are reported, and dropped if [`IndexSyncOptions::drop_undeclared`] is set.
+    /// ```ignore
+    /// let report = db.sync_indexes::<User>(None).await?;
+    /// for drift in report.drifted {
+    ///     println!("index {} differs from its declaration", drift.name);
+    /// }
+    /// ```
+    pub async fn sync_indexes<M>(
+        &self,
+        options: impl Into<Option<IndexSyncOptions>>,
+    ) -> Result<IndexSyncReport>
+    where
+        M: MongoModel + 'static,
+    {
+        let options = options.into().unwrap_or_default();
+        let collection = M::collection(self);
+        let mut report = IndexSyncReport {
+            collection: M::COLLECTION_NAME.to_string(),
+            ..Default::default()
+        };
+
+        let mut existing = match collection.list_indexes(None).await {
+            Ok(cursor) => cursor.try_collect::<Vec<_>>().await?,
+            // the collection does not exist yet, so it has no indexes
+            Err(err) if matches!(*err.kind, ErrorKind::Command(ref err) if err.code == 26) => {
+                Vec::new()
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        for declared in M::indexes() {
+            match existing
+                .iter()
+                .position(|index| same_keys(&declared, index))
+            {
+                Some(position) => {
+                    let existing = existing.remove(position);
+                    if !same_options(&declared, &existing) {
+                        report.drifted.push(IndexDrift {
+                            name: index_name(&existing),
+                            declared,
+                            existing,
+                        });
+                    }
+                }
+                None => {
+                    let name = collection.create_index(declared, None).await?.index_name;
+                    report.created.push(name);
+                }
+            }
+        }
+
+        for index in existing {
+            let name = index_name(&index);
+            if name == "_id_" {
+                continue;
+            }
+            if options.drop_undeclared {
+                collection.drop_index(name.as_str(), None).await?;
+                report.dropped.push(name.clone());
+            }
+            report.undeclared.push(name);
+        }
+
+        Ok(report)
+    }
+
+    /// Syncs the declared indexes of every MongoDB model (every struct with `#[model(mongo(...))]`) with their collections,
+    /// see [`Musty::sync_indexes`](crate::Musty::sync_indexes)
+    pub async fn sync_all_indexes(
+        &self,
+        options: impl Into<Option<IndexSyncOptions>>,
+    ) -> Result<Vec<IndexSyncReport>> {
+        let options = options.into().unwrap_or_default();
+        let mut reports = Vec::new();
+        for registration in inventory::iter::<IndexRegistration> {
+            reports.push((registration.sync)(self, options).await?);
+        }
+        Ok(reports)
+    }
+}
+
+/// Registers a model for [`Musty::sync_all_indexes`](crate::Musty::sync_all_indexes), submitted by the `model` macro
+#[doc(hidden)]
+pub struct IndexRegistration {
+    sync: for<'a> fn(&'a Db<Database>, IndexSyncOptions) -> BoxFuture<'a, Result<IndexSyncReport>>,
+}
+
+impl IndexRegistration {
+    pub const fn new<M: MongoModel + 'static>() -> Self {
+        Self {
+            sync: sync_model_indexes::<M>,
+        }
+    }
+}
+
+inventory::collect!(IndexRegistration);
+
+fn sync_model_indexes<M: MongoModel + 'static>(
+    db: &Db<Database>,
+    options: IndexSyncOptions,
+) -> BoxFuture<'_, Result<IndexSyncReport>> {
+    Box::pin(db.sync_indexes::<M>(options))
+}
+
+/// The name of an index, or the name the server generates for it if it is not set
+fn index_name(index: &IndexModel) -> String {
+    match index
+        .options
+        .as_ref()
+        .and_then(|options| options.name.clone())
+    {
+        Some(name) => name,
+        None => index
+            .keys
+            .iter()
+            .map(|(key, value)| match value {
+                Bson::String(value) => format!("{}_{}", key, value),
+                value => format!("{}_{}", key, value),
+            })
+            .collect::<Vec<_>>()
+            .join("_"),
+    }
+}
+
+/// Whether the keys of a declared index match the keys of an existing index.
+/// Text indexes are stored by the server with `_fts` & `_ftsx` keys and their fields as weights, so they are compared by their fields
+fn same_keys(declared: &IndexModel, existing: &IndexModel) -> bool {
+    let text_fields = |keys: &Document| {
+        keys.iter()
+            .filter(|(_, value)| matches!(value, Bson::String(kind) if kind == "text"))
+            .map(|(key, _)| key.clone())
+            .collect::<Vec<_>>()
+    };
+    let declared_text = text_fields(&declared.keys);
+
+    if declared_text.is_empty() {
+        return declared.keys.len() == existing.keys.len()
+            && declared.keys.iter().zip(existing.keys.iter()).all(
+                |((key, value), (other_key, other_value))| {
+                    key == other_key && same_value(value, other_value)
+                },
+            );
+    }
+
+    if !existing.keys.contains_key("_fts") {
+        return false;
+    }
+    let mut existing_text = existing
+        .options
+        .as_ref()
+        .and_then(|options| options.weights.as_ref())
+        .map(|weights| weights.keys().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    let mut declared_text = declared_text;
+    existing_text.sort();
+    declared_text.sort();
+    existing_text == declared_text
+}
+
+/// Whether the options of a declared index match the options of an existing index with the same keys
+fn same_options(declared: &IndexModel, existing: &IndexModel) -> bool {
+    let declared_options = declared.options.clone().unwrap_or_default();
+    let existing_options = existing.options.clone().unwrap_or_default();
+
+    declared_options.unique.unwrap_or(false) == existing_options.unique.unwrap_or(false)
+        && declared_options.sparse.unwrap_or(false) == existing_options.sparse.unwrap_or(false)
+        && declared_options.expire_after == existing_options.expire_after
+        && (declared_options.name.is_none() || declared_options.name == existing_options.name)
+}
+
+/// Compares index key values, ignoring the numeric type (the server may return `1` as an `Int32`, `Int64` or `Double`)
+fn same_value(value: &Bson, other: &Bson) -> bool {
+    let number = |value: &Bson| match value {
+        Bson::Int32(value) => Some(*value as f64),
+        Bson::Int64(value) => Some(*value as f64),
+        Bson::Double(value) => Some(*value),
+        _ => None,
+    };
+    match (number(value), number(other)) {
+        (Some(value), Some(other)) => value == other,
+        _ => value == other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bson::doc;
+    use mongodb::{options::IndexOptions, IndexModel};
+
+    use super::{index_name, same_keys, same_options};
+
+    fn index(keys: bson::Document, options: IndexOptions) -> IndexModel {
+        IndexModel::builder().keys(keys).options(options).build()
+    }
+
+    #[test]
+    fn names() {
+        let declared = index(doc! { "name": 1, "age": -1 }, IndexOptions::default());
+        assert_eq!(index_name(&declared), "name_1_age_-1");
+
+        let text = index(doc! { "bio": "text" }, IndexOptions::default());
+        assert_eq!(index_name(&text), "bio_text");
+    }
+
+    #[test]
+    fn keys() {
+        let declared = index(doc! { "name": 1, "age": -1 }, IndexOptions::default());
+        let existing = index(doc! { "name": 1.0, "age": -1_i64 }, IndexOptions::default());
+        assert!(same_keys(&declared, &existing));
+
+        let reordered = index(doc! { "age": -1, "name": 1 }, IndexOptions::default());
+        assert!(!same_keys(&declared, &reordered));
+
+        let text = index(doc! { "bio": "text" }, IndexOptions::default());
+        let existing_text = index(
+            doc! { "_fts": "text", "_ftsx": 1 },
+            IndexOptions::builder().weights(doc! { "bio": 1 }).build(),
+        );
+        assert!(same_keys(&text, &existing_text));
+        assert!(!same_keys(&declared, &existing_text));
+    }
+
+    #[test]
+    fn drift() {
+        let declared = index(
+            doc! { "expires_at": 1 },
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(60))
+                .build(),
+        );
+        let existing = index(
+            doc! { "expires_at": 1 },
+            IndexOptions::builder()
+                .name("expires_at_1".to_string())
+                .expire_after(Duration::from_secs(60))
+                .build(),
+        );
+        assert!(same_options(&declared, &existing));
+
+        let changed = index(
+            doc! { "expires_at": 1 },
+            IndexOptions::builder()
+                .expire_after(Duration::from_secs(120))
+                .unique(true)
+                .build(),
+        );
+        assert!(!same_options(&declared, &changed));
+    }
+}
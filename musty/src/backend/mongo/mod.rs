@@ -0,0 +1,995 @@
+use std::{pin::Pin, task::Poll};
+
+use async_trait::async_trait;
+use bson::{Bson, Document};
+use futures::{Stream, TryStreamExt};
+use mongodb::{
+    error::{ErrorKind, WriteError, WriteFailure},
+    options::{
+        ChangeStreamOptions, CollectionOptions, CountOptions, DeleteOptions, DistinctOptions,
+        EstimatedDocumentCountOptions, FindOneAndDeleteOptions, FindOneAndReplaceOptions,
+        FindOneAndUpdateOptions, FindOneOptions, FindOptions, ReadConcern, ReplaceOptions,
+        ReturnDocument, SelectionCriteria, UpdateModifications, WriteConcern,
+    },
+    results::DeleteResult,
+    Collection, Database, IndexModel,
+};
+use serde::de::DeserializeOwned;
+
+use crate::cursor::AfterLoad;
+use crate::model::{loaded, SaveOutcome};
+use crate::query::{scope_filter, DeletedScope, QueryOptions, Sort, SortOrder};
+use crate::update::Update;
+use crate::{
+    db::Db, field::Field, filter::Filter, model::Model, prelude::Context, prelude::MustyCursor,
+};
+use crate::{error::MustyError, id::IdGuard, prelude::Id, Result};
+
+use super::Backend;
+
+mod aggregate;
+mod change_stream;
+mod index;
+
+pub use aggregate::{Aggregate, AggregateCursor, GroupId};
+pub use change_stream::{ChangeEvent, MongoChangeStream};
+pub use index::{IndexDrift, IndexRegistration, IndexSyncOptions, IndexSyncReport};
+
+/// The collection holding the counters of the `sequence` id strategy, see [`generate`](crate::generate)
+const SEQUENCES_COLLECTION: &str = "musty_sequences";
+
+#[async_trait]
+impl Backend for Database {
+    type Filter = MongoFilter;
+    type Cursor<C: Model + 'static> = MongoCursor<C>;
+
+    async fn get_model_by_id<C, I>(&self, id: &Id<C, I>) -> Result<Option<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(&self) {
+            let id: Result<Bson> = id.try_into();
+            let filter = scoped::<C>(bson::doc!("_id": id?), DeletedScope::Include)?;
+            return Ok(collection.find_one(filter, None).await?);
+        }
+
+        Ok(None)
+    }
+
+    /// Finds the models with `$in` on the _id field
+    async fn get_models_by_ids<C, I>(&self, ids: &[Id<C, I>]) -> Result<Vec<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let ids = ids
+            .iter()
+            .filter(|id| !id.is_none())
+            .map(Bson::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(self) {
+            let filter = scoped::<C>(bson::doc! { "_id": { "$in": ids } }, DeletedScope::Include)?;
+            let cursor = collection.find(filter, None).await?;
+            return Ok(cursor.try_collect().await?);
+        }
+
+        Err(MustyError::Other(anyhow::anyhow!(
+            "Could not find models: no collection found"
+        )))
+    }
+
+    /// Save this model instance to the database
+    /// Models without an id are inserted with `insert_one`, and the id generated by the database is set on this model instance.
+    /// Otherwise, uses `upsert: true` with `replace_one` using the _id field of the document (and the discriminator of an enum model's variant) as a filter,
+    /// and the outcome is determined from the `upserted_id` and `modified_count` returned by the server.
+    /// Saving a variant with the id of a model of another variant fails with [`MustyError::IdConflict`]
+    /// Writes use the write concern of the model's collection, see [`MongoModel::write_concern`]
+    ///
+    /// Versioned models are instead replaced using the _id field and the expected version as a filter, see [`save_versioned`]
+    async fn save_model<C, I>(&self, model: &mut C) -> Result<SaveOutcome<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(&self) {
+            if let (Some(field), Some(version)) = (C::VERSION_FIELD, model.version()?) {
+                let next = version
+                    .checked_add(1)
+                    .ok_or(MustyError::VersionOutOfRange)?;
+                model.set_version(next)?;
+                let outcome = save_versioned(&collection, model, field, version).await;
+                if outcome.is_err() {
+                    model.set_version(version)?;
+                }
+                return outcome;
+            }
+
+            if model.id().is_none() {
+                let inserted_id = collection.insert_one(&(*model), None).await?.inserted_id;
+
+                let id: Id<C, C::Id> = bson::from_bson(inserted_id)?;
+                if id.is_none() {
+                    return Err(MustyError::MongoServerFailedToReturnObjectId);
+                }
+                model.set_id(id.clone());
+
+                return Ok(SaveOutcome::Inserted { id });
+            }
+
+            let replace_options = ReplaceOptions::builder().upsert(Some(true)).build();
+
+            let id: Bson = model.id().try_into()?;
+            let filter = scoped::<C>(bson::doc! { "_id": id }, DeletedScope::Include)?;
+            let result = collection
+                .replace_one(filter, &(*model), Some(replace_options))
+                .await
+                .map_err(|err| duplicate_id(err, MustyError::IdConflict))?;
+
+            if result.upserted_id.is_some() {
+                Ok(SaveOutcome::Inserted {
+                    id: model.id().clone(),
+                })
+            } else if result.modified_count > 0 {
+                Ok(SaveOutcome::Updated)
+            } else {
+                Ok(SaveOutcome::Unchanged)
+            }
+        } else {
+            Err(MustyError::Other(anyhow::anyhow!(
+                "Could not save model: no collection found"
+            )))
+        }
+    }
+
+    /// Increments the counter of the collection of `C` in the `musty_sequences` collection with `find_one_and_update`,
+    /// upserting the counter if the collection has none yet
+    async fn next_sequence<C, I>(&self) -> Result<i64>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let collection = C::contextualize_boxed_downcast::<Collection<C>>(self)?;
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let counter = self
+            .collection::<Document>(SEQUENCES_COLLECTION)
+            .find_one_and_update(
+                bson::doc! { "_id": collection.name() },
+                bson::doc! { "$inc": { "value": 1_i64 } },
+                options,
+            )
+            .await?
+            .ok_or(MustyError::MongoServerFailedToReturnUpdatedDoc)?;
+        Ok(counter.get_i64("value").map_err(anyhow::Error::from)?)
+    }
+
+    async fn delete_model<C, I>(&self, model: &mut C) -> Result<bool>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let id = model.id();
+        if id.is_none() {
+            return Err(MustyError::MongoModelIdRequiredForOperation);
+        }
+
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(&self) {
+            let id: Bson = id.try_into()?;
+            let filter = scoped::<C>(bson::doc! { "_id": id }, DeletedScope::Include)?;
+            collection
+                .delete_one(filter, None)
+                .await
+                .map(|res| res.deleted_count > 0)
+                .map_err(|e| e.into())
+        } else {
+            Err(MustyError::Other(anyhow::anyhow!(
+                "Could not delete model: no collection found"
+            )))
+        }
+    }
+
+    /// Sets the soft delete marker with `update_one` using the _id field of the model as a filter, without `upsert`
+    async fn set_model_deleted_at<C, I>(&self, model: &C) -> Result<bool>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+    {
+        let Some(field) = C::SOFT_DELETE_FIELD else {
+            return Ok(false);
+        };
+        let id = model.id();
+        if id.is_none() {
+            return Err(MustyError::MongoModelIdRequiredForOperation);
+        }
+
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(self) {
+            let id: Bson = id.try_into()?;
+            let marker = bson::to_document(model)?
+                .remove(field)
+                .unwrap_or(Bson::Null);
+            let filter = scoped::<C>(bson::doc! { "_id": id }, DeletedScope::Include)?;
+            let result = collection
+                .update_one(filter, bson::doc! { "$set": { field: marker } }, None)
+                .await?;
+            Ok(result.matched_count > 0)
+        } else {
+            Err(MustyError::Other(anyhow::anyhow!(
+                "Could not soft delete model: no collection found"
+            )))
+        }
+    }
+
+    async fn find_one<C, I, F>(&self, filter: F, options: Option<QueryOptions>) -> Result<Option<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync,
+    {
+        let options = options.unwrap_or_default();
+        let filter = scoped::<C>(filter.into().into_document()?, options.deleted)?;
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(&self) {
+            collection
+                .find_one(filter, FindOneOptions::from(options))
+                .await
+                .map_err(|e| e.into())
+        } else {
+            Err(MustyError::Other(anyhow::anyhow!(
+                "Could not find model: no collection found"
+            )))
+        }
+    }
+
+    async fn find_many<C, I, F>(
+        &self,
+        filter: F,
+        options: Option<QueryOptions>,
+    ) -> Result<Self::Cursor<C>>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync,
+    {
+        let options = options.unwrap_or_default();
+        let filter = scoped::<C>(filter.into().into_document()?, options.deleted)?;
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(self) {
+            Ok(collection
+                .find(filter, FindOptions::from(options))
+                .await
+                .map(MongoCursor::new)?)
+        } else {
+            Err(MustyError::Other(anyhow::anyhow!(
+                "Could not find models: no collection found"
+            )))
+        }
+    }
+
+    async fn count_models<C, I, F>(&self, filter: F, options: Option<QueryOptions>) -> Result<u64>
+    where
+        I: IdGuard,
+        C: Context<I, Self> + Model + 'static,
+        F: Into<Self::Filter> + Send + Sync,
+    {
+        let options = options.unwrap_or_default();
+        let filter = scoped::<C>(filter.into().into_document()?, options.deleted)?;
+        if let Ok(collection) = C::contextualize_boxed_downcast::<Collection<C>>(self) {
+            Ok(collection
+                .count_documents(filter, CountOptions::from(options))
+                .await?)
+        } else {
+            Err(MustyError::Other(anyhow::anyhow!(
+                "Could not count models: no collection found"
+            )))
+        }
+    }
+
+    fn with_hooks<C>(cursor: Self::Cursor<C>, db: Db<Self>) -> Self::Cursor<C>
+    where
+        C: Model + 'static,
+    {
+        cursor.with_after_load(db)
+    }
+}
+
+/// Saves a versioned model, which already has its incremented version set.
+/// The model is replaced only if the stored document still has the `expected` version,
+/// otherwise it is inserted if there is no document with its id, or fails with [`MustyError::VersionConflict`]
+async fn save_versioned<C: Model>(
+    collection: &Collection<C>,
+    model: &mut C,
+    field: &str,
+    expected: i64,
+) -> Result<SaveOutcome<C>> {
+    if model.id().is_none() {
+        let inserted_id = collection.insert_one(&(*model), None).await?.inserted_id;
+
+        let id: Id<C, C::Id> = bson::from_bson(inserted_id)?;
+        if id.is_none() {
+            return Err(MustyError::MongoServerFailedToReturnObjectId);
+        }
+        model.set_id(id.clone());
+
+        return Ok(SaveOutcome::Inserted { id });
+    }
+
+    let id: Bson = model.id().try_into()?;
+    let filter = scoped::<C>(
+        bson::doc! { "_id": id.clone(), field: expected },
+        DeletedScope::Include,
+    )?;
+    let result = collection.replace_one(filter, &(*model), None).await?;
+    if result.matched_count > 0 {
+        return Ok(SaveOutcome::Updated);
+    }
+
+    // nothing matched: either the document was saved with another version since, or it does not exist yet
+    let filter = scoped::<C>(bson::doc! { "_id": id.clone() }, DeletedScope::Include)?;
+    if collection.count_documents(filter, None).await? > 0 {
+        return Err(MustyError::VersionConflict);
+    }
+    // or a model of another variant has the id
+    if C::DISCRIMINATOR.is_some()
+        && collection
+            .count_documents(bson::doc! { "_id": id }, None)
+            .await?
+            > 0
+    {
+        return Err(MustyError::IdConflict);
+    }
+
+    // the document may have been inserted since it was counted
+    collection
+        .insert_one(&(*model), None)
+        .await
+        .map_err(|err| duplicate_id(err, MustyError::VersionConflict))?;
+    Ok(SaveOutcome::Inserted {
+        id: model.id().clone(),
+    })
+}
+
+/// Maps the duplicate key error of writing a model with an id which is already stored to `conflict`:
+/// [`MustyError::VersionConflict`] when a versioned model was inserted concurrently,
+/// or [`MustyError::IdConflict`] when a model of another variant has the id
+fn duplicate_id(err: mongodb::error::Error, conflict: MustyError) -> MustyError {
+    match err.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(WriteError {
+            code: 11000,
+            message,
+            ..
+        })) if message.contains("_id_") => conflict,
+        _ => err.into(),
+    }
+}
+
+/// Restricts a filter to the variant of the model (for the variants of enum models) and the given soft delete scope (for models with `#[model(soft_delete)]`)
+fn scoped<C: Model>(filter: Document, scope: DeletedScope) -> Result<Document> {
+    restricted(filter, scope_filter::<C>(scope))
+}
+
+fn restricted(filter: Document, scope: Option<Filter>) -> Result<Document> {
+    Ok(match scope {
+        Some(scope) if filter.is_empty() => scope.try_into()?,
+        Some(scope) => bson::doc! { "$and": [filter, Document::try_from(scope)?] },
+        None => filter,
+    })
+}
+
+/// The filter of MongoDB queries: a query document, or a [`Filter`] translated into one.
+/// A [`Filter`] which can not be translated fails the query with [`MustyError::InvalidFilter`]
+pub struct MongoFilter(Result<Document>);
+
+impl MongoFilter {
+    /// The query document, or the error of translating the filter
+    pub fn into_document(self) -> Result<Document> {
+        self.0
+    }
+}
+
+impl From<Document> for MongoFilter {
+    fn from(document: Document) -> Self {
+        Self(Ok(document))
+    }
+}
+
+impl From<Filter> for MongoFilter {
+    fn from(filter: Filter) -> Self {
+        Self(filter.try_into())
+    }
+}
+
+/// Translates a database-agnostic [`Filter`] into a MongoDB query document,
+/// failing with [`MustyError::InvalidFilter`] if one of its values could not be serialized or converted into BSON
+impl TryFrom<Filter> for Document {
+    type Error = MustyError;
+
+    fn try_from(filter: Filter) -> Result<Self> {
+        let bson = |value| bson_from_value(value, MustyError::InvalidFilter);
+        let mut document = Document::new();
+        match filter {
+            Filter::Eq(field, value) => {
+                document.insert(field, bson(value)?);
+            }
+            Filter::Ne(field, value) => {
+                document.insert(field, bson::doc! { "$ne": bson(value)? });
+            }
+            Filter::Gt(field, value) => {
+                document.insert(field, bson::doc! { "$gt": bson(value)? });
+            }
+            Filter::Lt(field, value) => {
+                document.insert(field, bson::doc! { "$lt": bson(value)? });
+            }
+            Filter::In(field, values) => {
+                let values = values.into_iter().map(bson).collect::<Result<Vec<_>>>()?;
+                document.insert(field, bson::doc! { "$in": values });
+            }
+            Filter::Exists(field, exists) => {
+                document.insert(field, bson::doc! { "$exists": exists });
+            }
+            Filter::And(filters) if filters.is_empty() => {}
+            Filter::And(filters) => {
+                let filters = filters
+                    .into_iter()
+                    .map(Document::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+                document.insert("$and", filters);
+            }
+            Filter::Or(filters) if filters.is_empty() => {
+                // `$or` requires at least one expression, and an empty `Or` matches nothing
+                document.insert("$nor", vec![Document::new()]);
+            }
+            Filter::Or(filters) => {
+                let filters = filters
+                    .into_iter()
+                    .map(Document::try_from)
+                    .collect::<Result<Vec<_>>>()?;
+                document.insert("$or", filters);
+            }
+            Filter::Not(filter) => {
+                // `$not` only applies to a single field, `$nor` negates any expression
+                document.insert("$nor", vec![Document::try_from(*filter)?]);
+            }
+            Filter::Invalid(err) => return Err(MustyError::InvalidFilter(err)),
+        }
+        Ok(document)
+    }
+}
+
+/// Translates a database-agnostic [`Update`] into a MongoDB update document, with the `$set`, `$inc` and `$unset` operators,
+/// failing with [`MustyError::InvalidUpdate`] if one of its values could not be serialized or converted into BSON
+impl TryFrom<Update> for Document {
+    type Error = MustyError;
+
+    fn try_from(update: Update) -> Result<Self> {
+        fn add(operators: &mut Document, update: Update) -> Result<()> {
+            let (operator, field, value) = match update {
+                Update::Set(field, value) => (
+                    "$set",
+                    field,
+                    bson_from_value(value, MustyError::InvalidUpdate)?,
+                ),
+                Update::Inc(field, value) => (
+                    "$inc",
+                    field,
+                    bson_from_value(value, MustyError::InvalidUpdate)?,
+                ),
+                Update::Unset(field) => ("$unset", field, Bson::String(String::new())),
+                Update::And(updates) => {
+                    return updates
+                        .into_iter()
+                        .try_for_each(|update| add(operators, update))
+                }
+                Update::Invalid(err) => return Err(MustyError::InvalidUpdate(err)),
+            };
+            match operators.get_document_mut(operator) {
+                Ok(fields) => {
+                    fields.insert(field, value);
+                }
+                Err(_) => {
+                    operators.insert(operator, bson::doc! { field: value });
+                }
+            }
+            Ok(())
+        }
+
+        let mut operators = Document::new();
+        add(&mut operators, update)?;
+        Ok(operators)
+    }
+}
+
+/// Maps database-agnostic [`QueryOptions`] to MongoDB's options for finding multiple documents
+impl From<QueryOptions> for FindOptions {
+    fn from(options: QueryOptions) -> Self {
+        FindOptions::builder()
+            .sort(sort_document(&options.sort))
+            .limit(options.limit.map(|limit| limit as i64))
+            .skip(options.skip)
+            .projection(projection_document(options.projection.as_deref()))
+            .build()
+    }
+}
+
+/// Maps database-agnostic [`QueryOptions`] to MongoDB's options for finding a single document, the limit is ignored
+impl From<QueryOptions> for FindOneOptions {
+    fn from(options: QueryOptions) -> Self {
+        FindOneOptions::builder()
+            .sort(sort_document(&options.sort))
+            .skip(options.skip)
+            .projection(projection_document(options.projection.as_deref()))
+            .build()
+    }
+}
+
+/// Maps database-agnostic [`QueryOptions`] to MongoDB's options for counting documents, the sort and projection are ignored
+impl From<QueryOptions> for CountOptions {
+    fn from(options: QueryOptions) -> Self {
+        CountOptions::builder()
+            .skip(options.skip)
+            .limit(options.limit)
+            .build()
+    }
+}
+
+fn sort_document(sort: &[Sort]) -> Option<Document> {
+    if sort.is_empty() {
+        return None;
+    }
+
+    Some(
+        sort.iter()
+            .map(|sort| {
+                let order = match sort.order {
+                    SortOrder::Ascending => 1,
+                    SortOrder::Descending => -1,
+                };
+                (sort.field.clone(), Bson::Int32(order))
+            })
+            .collect(),
+    )
+}
+
+fn projection_document(projection: Option<&[String]>) -> Option<Document> {
+    projection.map(|fields| {
+        fields
+            .iter()
+            .map(|field| (field.clone(), Bson::Int32(1)))
+            .collect()
+    })
+}
+
+/// Converts a serialized filter or update value into BSON, reading it as extended JSON so that types like `ObjectId` round-trip
+fn bson_from_value(value: serde_json::Value, error: fn(String) -> MustyError) -> Result<Bson> {
+    Bson::try_from(value.clone())
+        .or_else(|_| bson::to_bson(&value))
+        .map_err(|err| error(err.to_string()))
+}
+
+impl<I, M> Context<I, Database> for M
+where
+    M: MongoModel + 'static,
+    I: IdGuard,
+{
+    type Output = Collection<Self>;
+
+    /// The collection for this model, named [`MongoModel::COLLECTION_NAME`] with the [`MongoModel::collection_options`]
+    fn contextualize(db: &Database) -> Self::Output {
+        db.collection_with_options(Self::COLLECTION_NAME, Self::collection_options())
+    }
+}
+
+/// Exposes MongoDB operations for a model.
+#[async_trait]
+pub trait MongoModel
+where
+    Self: Model,
+{
+    /// The collection name for this model
+    /// Automatically implemented
+    /// Can be set using `#[model(collection_name = "name")]` on the model struct
+    const COLLECTION_NAME: &'static str;
+
+    /// The read concern for MongoDB for this collection
+    fn read_concern() -> Option<ReadConcern> {
+        None
+    }
+
+    /// The write concern for MongoDB for this collection
+    fn write_concern() -> Option<WriteConcern> {
+        None
+    }
+
+    /// The selection criteria for MongoDB for this collection
+    fn selection_criteria() -> Option<SelectionCriteria> {
+        None
+    }
+
+    /// The indexes declared on this model, created by [`Musty::sync_indexes`](crate::Musty::sync_indexes)
+    /// Automatically implemented from the index attributes:
+    /// `#[musty(mongo(index, unique, sparse, text, ttl = "30d"))]` on a field,
+    /// or `#[model(mongo(index(keys = "name, -age", unique)))]` on the model struct for compound indexes
+    fn indexes() -> Vec<IndexModel> {
+        Vec::new()
+    }
+
+    /// The options for this model's collection, built from the read concern, write concern and selection criteria.
+    /// Used by every operation on the collection, including the generic [`Model`] operations (ex: `get_by_id`, `save`)
+    fn collection_options() -> CollectionOptions {
+        CollectionOptions::builder()
+            .selection_criteria(Self::selection_criteria())
+            .read_concern(Self::read_concern())
+            .write_concern(Self::write_concern())
+            .build()
+    }
+
+    /// The collection for this model, named [`MongoModel::COLLECTION_NAME`] with the [`MongoModel::collection_options`]
+    /// The generic [`Model`] operations (ex: `get_by_id`, `save`) only have the MongoDB database, so they build the same collection from its name and options:
+    /// override `collection_options` rather than this function to change the collection of every operation
+    fn collection(db: &Db<Database>) -> Collection<Self> {
+        db.inner
+            .collection_with_options(Self::COLLECTION_NAME, Self::collection_options())
+    }
+
+    /// Converts the model to a BSON document
+    fn document_from_model(&self) -> Result<Document> {
+        match bson::to_bson(&self)? {
+            bson::Bson::Document(doc) => Ok(doc),
+            _ => Err(MustyError::Other(anyhow::anyhow!(
+                "Could not convert model to document"
+            ))),
+        }
+    }
+
+    /// Converts a BSON document to this model type
+    fn model_from_document(document: Document) -> Result<Self> {
+        Ok(bson::from_bson::<Self>(bson::Bson::Document(document))?)
+    }
+
+    /// Find instances of this model type that match the given filter (ex `bson::doc! { "name": "John" }`)
+    /// Returns a `MongoCursor` which can be used to iterate over the results
+    /// Use `futures::StreamExt` to iterate over the results using
+    /// `while let Some(result) = cursor.next().await {}`
+    /// Soft-deleted models are not found, see [`MongoModel::find_with_deleted`]
+    async fn find<F, O>(db: &Db<Database>, filter: F, options: O) -> Result<MongoCursor<Self>>
+    where
+        Self: 'static,
+        F: Into<Option<Document>> + Send,
+        O: Into<Option<FindOptions>> + Send,
+    {
+        let filter = restricted(
+            filter.into().unwrap_or_default(),
+            DeletedScope::Exclude.filter::<Self>(),
+        )?;
+        Self::find_with_deleted(db, filter, options).await
+    }
+
+    /// Find instances of this model type that match the given filter, including soft-deleted models
+    /// (filter on the soft delete marker to only find soft-deleted models)
+    async fn find_with_deleted<F, O>(
+        db: &Db<Database>,
+        filter: F,
+        options: O,
+    ) -> Result<MongoCursor<Self>>
+    where
+        Self: 'static,
+        F: Into<Option<Document>> + Send,
+        O: Into<Option<FindOptions>> + Send,
+    {
+        let filter = scoped::<Self>(filter.into().unwrap_or_default(), DeletedScope::Include)?;
+        let cursor = Self::collection(db)
+            .find(filter, options)
+            .await
+            .map(MongoCursor::new)?;
+        if Self::HOOKS {
+            return Ok(cursor.with_after_load(db.clone()));
+        }
+        Ok(cursor)
+    }
+
+    /// Count the documents of this model type that match the given filter (ex `bson::doc! { "name": "John" }`), with `countDocuments`
+    /// Soft-deleted models are not counted, and the variants of an enum model only count the models of their variant
+    async fn count_documents<F, O>(db: &Db<Database>, filter: F, options: O) -> Result<u64>
+    where
+        F: Into<Option<Document>> + Send,
+        O: Into<Option<CountOptions>> + Send,
+    {
+        let filter = scoped::<Self>(filter.into().unwrap_or_default(), DeletedScope::Exclude)?;
+        Ok(Self::collection(db)
+            .count_documents(filter, options)
+            .await?)
+    }
+
+    /// Estimate the number of documents in the collection of this model from its metadata, which is faster than counting them
+    /// The estimate includes soft-deleted models, and the models of every variant for enum models
+    async fn estimated_document_count<O>(db: &Db<Database>, options: O) -> Result<u64>
+    where
+        O: Into<Option<EstimatedDocumentCountOptions>> + Send,
+    {
+        Ok(Self::collection(db)
+            .estimated_document_count(options)
+            .await?)
+    }
+
+    /// Find the distinct values of a field (ex: `Post::fields().author`) in the documents that match the given filter,
+    /// deserialized into the type of the field. Soft-deleted models are left out
+    async fn distinct<T>(
+        db: &Db<Database>,
+        field: Field<Self, T>,
+        filter: impl Into<Option<Document>> + Send,
+        options: impl Into<Option<DistinctOptions>> + Send,
+    ) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let filter = scoped::<Self>(filter.into().unwrap_or_default(), DeletedScope::Exclude)?;
+        let values = Self::collection(db)
+            .distinct(field.path(), filter, options)
+            .await?;
+        Ok(values
+            .into_iter()
+            .map(bson::from_bson)
+            .collect::<std::result::Result<_, _>>()?)
+    }
+
+    /// Start an aggregation pipeline on the collection of this model, see [`Aggregate`]
+    fn aggregate(db: &Db<Database>) -> Aggregate<Self> {
+        Aggregate::new(db)
+    }
+
+    /// Watch the changes made to models of this type, as a stream of [`ChangeEvent`]s (change streams need a replica set or a sharded cluster).
+    /// The `pipeline` filters or transforms the change events (ex: `[doc! { "$match": { "operationType": "insert" } }]`),
+    /// and the `options` can look up the updated models (`full_document`) or restart a stream from a resume token (`start_after`),
+    /// see [`MongoChangeStream`].
+    /// The variants of an enum model only get the changes of their variant, except for deletes and updates which are not looked up
+    async fn watch<P, O>(
+        db: &Db<Database>,
+        pipeline: P,
+        options: O,
+    ) -> Result<MongoChangeStream<Self>>
+    where
+        Self: 'static,
+        P: IntoIterator<Item = Document> + Send,
+        O: Into<Option<ChangeStreamOptions>> + Send,
+    {
+        // the changes without a model (deletes and updates which are not looked up) can not be told apart by variant
+        let scope = scope_filter::<Self>(DeletedScope::Include)
+            .map(|scope| {
+                let filter = Filter::Eq("fullDocument".to_string(), serde_json::Value::Null)
+                    .or(scope.nested("fullDocument"));
+                Document::try_from(filter).map(|filter| bson::doc! { "$match": filter })
+            })
+            .transpose()?;
+        let pipeline = scope.into_iter().chain(pipeline).collect::<Vec<_>>();
+        let stream = Self::collection(db).watch(pipeline, options).await?;
+        Ok(MongoChangeStream::new(stream))
+    }
+
+    /// Find a single document and replace it
+    async fn find_one_and_replace<F, O>(
+        db: &Db<Database>,
+        filter: F,
+        replacement: &Self,
+        options: O,
+    ) -> Result<Option<Self>>
+    where
+        F: Into<MongoFilter> + Send,
+        O: Into<Option<FindOneAndReplaceOptions>> + Send,
+    {
+        let model = Self::collection(db)
+            .find_one_and_replace(
+                scoped::<Self>(filter.into().into_document()?, DeletedScope::Include)?,
+                replacement,
+                options,
+            )
+            .await?;
+        loaded(db, model).await
+    }
+
+    /// Find a single document and update it
+    async fn find_one_and_update<F, U, O>(
+        db: &Db<Database>,
+        filter: F,
+        update: U,
+        options: O,
+    ) -> Result<Option<Self>>
+    where
+        F: Into<MongoFilter> + Send,
+        U: Into<UpdateModifications> + Send,
+        O: Into<Option<FindOneAndUpdateOptions>> + Send,
+    {
+        let model = Self::collection(db)
+            .find_one_and_update(
+                scoped::<Self>(filter.into().into_document()?, DeletedScope::Include)?,
+                update,
+                options,
+            )
+            .await?;
+        loaded(db, model).await
+    }
+
+    /// Find a single document and delete it
+    async fn find_one_and_delete<F, O>(
+        db: &Db<Database>,
+        filter: F,
+        options: O,
+    ) -> Result<Option<Self>>
+    where
+        F: Into<MongoFilter> + Send,
+        O: Into<Option<FindOneAndDeleteOptions>> + Send,
+    {
+        let model = Self::collection(db)
+            .find_one_and_delete(
+                scoped::<Self>(filter.into().into_document()?, DeletedScope::Include)?,
+                options,
+            )
+            .await?;
+        loaded(db, model).await
+    }
+
+    /// Deletes all documents in the collection that match the given filter
+    async fn delete_many<F, O>(db: &Db<Database>, filter: F, options: O) -> Result<DeleteResult>
+    where
+        F: Into<MongoFilter> + Send,
+        O: Into<Option<DeleteOptions>> + Send,
+    {
+        Ok(Self::collection(db)
+            .delete_many(
+                scoped::<Self>(filter.into().into_document()?, DeletedScope::Include)?,
+                options,
+            )
+            .await?)
+    }
+}
+
+/// A stream of models found in MongoDB, wrapping a `mongodb::Cursor`
+pub struct MongoCursor<M>
+where
+    M: Model,
+{
+    cursor: mongodb::Cursor<M>,
+    after_load: Option<AfterLoad<Database, M>>,
+}
+
+impl<M> Unpin for MongoCursor<M>
+where
+    M: Model,
+{
+}
+
+impl<M> MongoCursor<M>
+where
+    M: Model,
+{
+    pub fn new(cursor: mongodb::Cursor<M>) -> Self {
+        Self {
+            cursor,
+            after_load: None,
+        }
+    }
+}
+
+impl<M> MongoCursor<M>
+where
+    M: Model + 'static,
+{
+    /// Runs the `after_load` hook of every model loaded by this cursor, see [`ModelHooks`](crate::prelude::ModelHooks)
+    pub fn with_after_load(mut self, db: Db<Database>) -> Self {
+        self.after_load = Some(AfterLoad::new(db));
+        self
+    }
+}
+
+impl<M> MustyCursor<M> for MongoCursor<M> where M: Model + 'static {}
+
+impl<M> Stream for MongoCursor<M>
+where
+    M: Model + 'static,
+{
+    type Item = Result<M>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let cursor = &mut this.cursor;
+        let mut next = |cx: &mut std::task::Context<'_>| match Pin::new(&mut *cursor).poll_next(cx)
+        {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(MustyError::from(err)))),
+            Poll::Ready(Some(Ok(model))) => Poll::Ready(Some(Ok(model))),
+        };
+
+        match this.after_load.as_mut() {
+            Some(after_load) => after_load.poll_next(cx, next),
+            None => next(cx),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bson::{doc, oid::ObjectId, Document};
+    use mongodb::options::FindOptions;
+
+    use crate::error::MustyError;
+    use crate::filter::Filter;
+    use crate::query::{QueryOptions, Sort};
+    use crate::update::Update;
+
+    #[test]
+    fn query_options_to_find_options() {
+        let options = FindOptions::from(
+            QueryOptions::new()
+                .sort(Sort::desc("age"))
+                .sort(Sort::asc("name"))
+                .skip(20)
+                .limit(10)
+                .project("name"),
+        );
+
+        assert_eq!(options.sort, Some(doc! { "age": -1, "name": 1 }));
+        assert_eq!(options.skip, Some(20));
+        assert_eq!(options.limit, Some(10));
+        assert_eq!(options.projection, Some(doc! { "name": 1 }));
+    }
+
+    #[test]
+    fn filter_to_document() {
+        let id = ObjectId::new();
+        let filter = Filter::eq("_id", id)
+            .and(Filter::gt("age", 18))
+            .and(Filter::in_("name", ["jonah", "alex"]))
+            .and(!Filter::exists("email", true))
+            .and(Filter::ne("role", "admin").or(Filter::lt("age", 65)));
+
+        assert_eq!(
+            Document::try_from(filter).unwrap(),
+            doc! {
+                "$and": [
+                    { "_id": id },
+                    { "age": { "$gt": 18 } },
+                    { "name": { "$in": ["jonah", "alex"] } },
+                    { "$nor": [{ "email": { "$exists": true } }] },
+                    { "$or": [{ "role": { "$ne": "admin" } }, { "age": { "$lt": 65 } }] },
+                ]
+            }
+        );
+    }
+
+    #[test]
+    fn update_to_document() {
+        let update = Update::set("name", "jonah")
+            .and(Update::inc("age", 1))
+            .and(Update::unset("email"))
+            .and(Update::set("role", "admin"));
+
+        assert_eq!(
+            Document::try_from(update).unwrap(),
+            doc! {
+                "$set": { "name": "jonah", "role": "admin" },
+                "$inc": { "age": 1 },
+                "$unset": { "email": "" },
+            }
+        );
+        assert!(matches!(
+            Document::try_from(Update::Invalid("invalid value".to_string())),
+            Err(MustyError::InvalidUpdate(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_filter_to_document() {
+        let filter = Filter::eq("name", "jonah").and(Filter::Invalid("invalid value".to_string()));
+        assert!(matches!(
+            Document::try_from(filter),
+            Err(MustyError::InvalidFilter(err)) if err == "invalid value"
+        ));
+    }
+}
@@ -0,0 +1,104 @@
+use std::time::SystemTime;
+
+/// The source of the current time for a [`Musty`](crate::Musty) database, used to fill in the timestamps of models on save.
+///
+/// Defaults to [`SystemClock`], and can be replaced with [`Musty::with_clock`](crate::Musty::with_clock) (ex: to freeze time in tests with a [`FixedClock`]).
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// A clock reading the system time
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock which always returns the same time
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedClock(pub SystemTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+/// A type which can be used for the `#[musty(created_at)]` and `#[musty(updated_at)]` fields of a model.
+///
+/// Implemented for `SystemTime`, `bson::DateTime` (stored as a native BSON datetime in MongoDB), and `Option`s of those.
+/// The timestamps of MongoDB models must be `bson::DateTime`s, see [`BsonTimestamp`].
+pub trait Timestamp {
+    fn from_system_time(time: SystemTime) -> Self;
+
+    /// Whether the timestamp has not been set yet: `None`, or the Unix epoch
+    fn is_unset(&self) -> bool;
+
+    /// The time of the timestamp, or `None` if it is not set
+    fn to_system_time(&self) -> Option<SystemTime>;
+}
+
+impl Timestamp for SystemTime {
+    fn from_system_time(time: SystemTime) -> Self {
+        time
+    }
+
+    fn is_unset(&self) -> bool {
+        *self == SystemTime::UNIX_EPOCH
+    }
+
+    fn to_system_time(&self) -> Option<SystemTime> {
+        (!self.is_unset()).then_some(*self)
+    }
+}
+
+#[cfg(feature = "bson")]
+impl Timestamp for bson::DateTime {
+    fn from_system_time(time: SystemTime) -> Self {
+        bson::DateTime::from_system_time(time)
+    }
+
+    fn is_unset(&self) -> bool {
+        self.timestamp_millis() == 0
+    }
+
+    fn to_system_time(&self) -> Option<SystemTime> {
+        (!self.is_unset()).then(|| bson::DateTime::to_system_time(*self))
+    }
+}
+
+/// A [`Timestamp`] stored as a native BSON datetime, which the timestamps and the soft delete marker of MongoDB models must be.
+///
+/// `SystemTime` is serialized as a struct of seconds and nanoseconds, which can not be sorted or compared with dates in MongoDB
+#[cfg(feature = "bson")]
+#[diagnostic::on_unimplemented(
+    message = "the timestamps of MongoDB models must be `bson::DateTime` or `Option<bson::DateTime>`, found `{Self}`",
+    label = "not stored as a BSON datetime"
+)]
+pub trait BsonTimestamp: Timestamp {}
+
+#[cfg(feature = "bson")]
+impl BsonTimestamp for bson::DateTime {}
+
+#[cfg(feature = "bson")]
+impl BsonTimestamp for Option<bson::DateTime> {}
+
+impl<T: Timestamp> Timestamp for Option<T> {
+    fn from_system_time(time: SystemTime) -> Self {
+        Some(T::from_system_time(time))
+    }
+
+    fn is_unset(&self) -> bool {
+        match self {
+            Some(time) => time.is_unset(),
+            None => true,
+        }
+    }
+
+    fn to_system_time(&self) -> Option<SystemTime> {
+        self.as_ref().and_then(T::to_system_time)
+    }
+}
@@ -0,0 +1,293 @@
+//! Relay-style connections of models for GraphQL, with keyset pagination.
+//!
+//! A [`ConnectionQuery`] finds the models matching a filter, in the order of its sort keys, and pages through them
+//! with the `after`, `before`, `first` and `last` arguments of a connection field:
+//! ```ignore
+//! #[Object]
+//! impl Query {
+//!     async fn posts(
+//!         &self,
+//!         ctx: &Context<'_>,
+//!         after: Option<String>,
+//!         before: Option<String>,
+//!         first: Option<i32>,
+//!         last: Option<i32>,
+//!     ) -> async_graphql::Result<Connection<String, Post>> {
+//!         let db = ctx.data_unchecked::<Musty<mongodb::Database>>();
+//!         Ok(ConnectionQuery::new(filter!(Post, published == true))
+//!             .sort(Post::fields().created_at.desc())
+//!             .after(after)
+//!             .before(before)
+//!             .first(first)
+//!             .last(last)
+//!             .load(db)
+//!             .await?)
+//!     }
+//! }
+//! ```
+//! The cursor of a model is opaque to clients: it encodes the values of the sort keys of the model, and its id.
+//! Pages are found with a filter on these values rather than by skipping models, so pages stay consistent when models are added or removed,
+//! and are as fast to find at the end of a collection as at its start.
+//! The id breaks ties between models with the same sort values, so sort keys should not be missing or `null`.
+
+use async_graphql::{
+    connection::{Connection, Edge},
+    OutputType,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures::TryStreamExt;
+use serde_json::Value;
+
+use crate::{
+    db::Db,
+    filter::{lookup, to_value, Filter},
+    prelude::{Backend, Context, DeletedScope, QueryOptions, Sort, SortOrder},
+    Model, MustyError, Result,
+};
+
+/// The name of the id in queries, which breaks ties between models with the same sort values
+const ID_FIELD: &str = "_id";
+
+/// A page of the models matching a filter, as a GraphQL connection. See [`connection`](crate::connection)
+#[derive(Clone, Debug)]
+pub struct ConnectionQuery {
+    filter: Filter,
+    sort: Vec<Sort>,
+    deleted: DeletedScope,
+    after: Option<String>,
+    before: Option<String>,
+    first: Option<i32>,
+    last: Option<i32>,
+}
+
+impl ConnectionQuery {
+    /// The models matching the filter, sorted by id until sort keys are added
+    pub fn new(filter: impl Into<Filter>) -> Self {
+        Self {
+            filter: filter.into(),
+            sort: Vec::new(),
+            deleted: DeletedScope::default(),
+            after: None,
+            before: None,
+            first: None,
+            last: None,
+        }
+    }
+
+    /// Sorts by the given key, after any previously added sort keys
+    pub fn sort(mut self, sort: impl Into<Sort>) -> Self {
+        self.sort.push(sort.into());
+        self
+    }
+
+    /// Also finds soft-deleted models
+    pub fn with_deleted(mut self) -> Self {
+        self.deleted = DeletedScope::Include;
+        self
+    }
+
+    /// Only finds soft-deleted models
+    pub fn only_deleted(mut self) -> Self {
+        self.deleted = DeletedScope::Only;
+        self
+    }
+
+    /// Only finds the models after the model with this cursor
+    pub fn after(mut self, after: Option<String>) -> Self {
+        self.after = after;
+        self
+    }
+
+    /// Only finds the models before the model with this cursor
+    pub fn before(mut self, before: Option<String>) -> Self {
+        self.before = before;
+        self
+    }
+
+    /// Returns at most the first `first` models
+    pub fn first(mut self, first: Option<i32>) -> Self {
+        self.first = first;
+        self
+    }
+
+    /// Returns at most the last `last` models
+    pub fn last(mut self, last: Option<i32>) -> Self {
+        self.last = last;
+        self
+    }
+
+    /// Find the page of models from a database.
+    /// Fails with [`MustyError::InvalidPagination`] if a cursor is invalid, or if `first` or `last` is negative
+    pub async fn load<M, B>(self, db: &Db<B>) -> Result<Connection<String, M>>
+    where
+        M: Model + Context<M::Id, B> + OutputType + 'static,
+        B: Backend,
+        Filter: Into<B::Filter>,
+    {
+        let first = count(self.first)?;
+        let last = count(self.last)?;
+        let mut sort = self.sort;
+        if !sort.iter().any(|sort| sort.field == ID_FIELD) {
+            sort.push(Sort::asc(ID_FIELD));
+        }
+
+        let mut filter = self.filter;
+        if let Some(after) = self.after.as_deref() {
+            filter = filter.and(keyset(&sort, decode(after, sort.len())?, true));
+        }
+        if let Some(before) = self.before.as_deref() {
+            filter = filter.and(keyset(&sort, decode(before, sort.len())?, false));
+        }
+
+        // without `first`, the last models are found by reversing the sort
+        let backward = first.is_none() && last.is_some();
+        let limit = if backward { last } else { first };
+        let options = QueryOptions {
+            sort: match backward {
+                true => sort.iter().map(reverse).collect(),
+                false => sort.clone(),
+            },
+            // one more model tells if there is a next page
+            limit: limit.map(|limit| limit as u64 + 1),
+            deleted: self.deleted,
+            ..QueryOptions::default()
+        };
+        let mut models: Vec<M> = M::find_many(db, filter, options)
+            .await?
+            .try_collect()
+            .await?;
+
+        let more = limit.is_some_and(|limit| models.len() > limit);
+        if let Some(limit) = limit {
+            models.truncate(limit);
+        }
+        let (mut has_previous_page, has_next_page) = match backward {
+            true => {
+                models.reverse();
+                (more, self.before.is_some())
+            }
+            false => (self.after.is_some(), more),
+        };
+        if let (Some(_), Some(last)) = (first, last) {
+            if models.len() > last {
+                models.drain(..models.len() - last);
+                has_previous_page = true;
+            }
+        }
+
+        let mut connection = Connection::new(has_previous_page, has_next_page);
+        for model in models {
+            connection
+                .edges
+                .push(Edge::new(cursor(&sort, &model)?, model));
+        }
+        Ok(connection)
+    }
+}
+
+/// The cursor of a model: the values of its sort keys, as base64-encoded JSON
+fn cursor<M: Model>(sort: &[Sort], model: &M) -> Result<String> {
+    let mut document = to_value(model)?;
+    if let Value::Object(fields) = &mut document {
+        fields.insert(ID_FIELD.to_string(), to_value(model.id())?);
+    }
+    let values = sort
+        .iter()
+        .map(|sort| {
+            lookup(&document, &sort.field)
+                .cloned()
+                .unwrap_or(Value::Null)
+        })
+        .collect::<Vec<_>>();
+    Ok(URL_SAFE_NO_PAD.encode(serde_json::to_vec(&values)?))
+}
+
+/// The values of the sort keys in a cursor
+fn decode(cursor: &str, len: usize) -> Result<Vec<Value>> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|json| serde_json::from_slice::<Vec<Value>>(&json).ok())
+        .filter(|values| values.len() == len)
+        .ok_or(MustyError::InvalidPagination("invalid cursor"))
+}
+
+/// The filter for the models after (or before) the model with the given sort values, in the order of the sort keys:
+/// models with a greater first sort value, or with the same first sort value and a greater second sort value, and so on
+fn keyset(sort: &[Sort], values: Vec<Value>, after: bool) -> Filter {
+    let keys = sort.iter().zip(values).collect::<Vec<_>>();
+    let filters = (0..keys.len())
+        .map(|i| {
+            let mut filters = keys[..i]
+                .iter()
+                .map(|(sort, value)| Filter::Eq(sort.field.clone(), value.clone()))
+                .collect::<Vec<_>>();
+            let (sort, value) = &keys[i];
+            let field = sort.field.clone();
+            filters.push(match (sort.order == SortOrder::Ascending) == after {
+                true => Filter::Gt(field, value.clone()),
+                false => Filter::Lt(field, value.clone()),
+            });
+            Filter::And(filters)
+        })
+        .collect();
+    Filter::Or(filters)
+}
+
+fn reverse(sort: &Sort) -> Sort {
+    Sort {
+        field: sort.field.clone(),
+        order: match sort.order {
+            SortOrder::Ascending => SortOrder::Descending,
+            SortOrder::Descending => SortOrder::Ascending,
+        },
+    }
+}
+
+/// The number of models asked for by `first` or `last`
+fn count(count: Option<i32>) -> Result<Option<usize>> {
+    count
+        .map(usize::try_from)
+        .transpose()
+        .map_err(|_| MustyError::InvalidPagination("`first` and `last` must not be negative"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{decode, keyset};
+    use crate::{filter::Filter, prelude::Sort};
+
+    #[test]
+    fn keyset_filters() {
+        let sort = [Sort::desc("score"), Sort::asc("_id")];
+        assert_eq!(
+            keyset(&sort, vec![json!(10), json!(3)], true),
+            Filter::Or(vec![
+                Filter::And(vec![Filter::Lt("score".to_string(), json!(10))]),
+                Filter::And(vec![
+                    Filter::Eq("score".to_string(), json!(10)),
+                    Filter::Gt("_id".to_string(), json!(3)),
+                ]),
+            ])
+        );
+        assert_eq!(
+            keyset(&sort[1..], vec![json!(3)], false),
+            Filter::Or(vec![Filter::And(vec![Filter::Lt(
+                "_id".to_string(),
+                json!(3)
+            )])])
+        );
+    }
+
+    #[test]
+    fn invalid_cursors() {
+        assert!(decode("not a cursor", 1).is_err());
+        // a cursor for other sort keys
+        let cursor =
+            base64::Engine::encode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, b"[1, 2]");
+        assert!(decode(&cursor, 1).is_err());
+        assert_eq!(decode(&cursor, 2).unwrap(), vec![json!(1), json!(2)]);
+    }
+}
@@ -1,13 +1,63 @@
-use futures::Stream;
+use std::{future::Future, pin::Pin, task::Poll};
 
-use crate::{prelude::Model};
+use futures::{future::BoxFuture, Stream};
+
+use crate::{
+    db::Db,
+    hooks::Hook,
+    prelude::{Backend, Model},
+    Result,
+};
 
 /// A simple wrapper for the cursor for musty models.
 /// Used when finding multiple models
 /// For MongoDB, this is a `mongodb::Cursor`
+///
+/// Use `futures::StreamExt` to iterate over the results using
+/// `while let Some(result) = cursor.next().await {}`
 pub trait MustyCursor<M>
 where
-    Self: Unpin + Stream + Sized,
+    Self: Unpin + Stream<Item = Result<M>> + Sized,
     M: Model,
 {
 }
+
+/// Runs the `after_load` hook of every model loaded by a cursor, see [`ModelHooks`](crate::prelude::ModelHooks)
+pub(crate) struct AfterLoad<B: Backend, M> {
+    db: Db<B>,
+    pending: Option<BoxFuture<'static, Result<M>>>,
+}
+
+impl<B: Backend, M: Model + 'static> AfterLoad<B, M> {
+    pub(crate) fn new(db: Db<B>) -> Self {
+        Self { db, pending: None }
+    }
+
+    /// Polls the hook of the last loaded model if it is still running, otherwise loads the next model with `next` and starts its hook
+    pub(crate) fn poll_next(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+        mut next: impl FnMut(&mut std::task::Context<'_>) -> Poll<Option<Result<M>>>,
+    ) -> Poll<Option<Result<M>>> {
+        loop {
+            if let Some(pending) = self.pending.as_mut() {
+                let model = match Pin::new(pending).poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(model) => model,
+                };
+                self.pending = None;
+                return Poll::Ready(Some(model));
+            }
+
+            let mut model = match next(cx) {
+                Poll::Ready(Some(Ok(model))) => model,
+                other => return other,
+            };
+            let db = self.db.clone();
+            self.pending = Some(Box::pin(async move {
+                model.run_hook(&db, Hook::AfterLoad).await?;
+                Ok(model)
+            }));
+        }
+    }
+}
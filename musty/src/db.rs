@@ -1,9 +1,43 @@
+use std::{sync::Arc, time::SystemTime};
+
+use crate::clock::{Clock, SystemClock};
+use crate::generate::Snowflake;
 use crate::prelude::Backend;
 
 /// Wrapper struct for a database connection.
 #[derive(Clone)]
 pub struct Db<T: Backend> {
     pub(crate) inner: T,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) snowflake: Arc<Snowflake>,
+}
+
+impl<T: Backend> Db<T> {
+    pub(crate) fn from_backend(inner: T) -> Self {
+        Db {
+            inner,
+            clock: Arc::new(SystemClock),
+            snowflake: Arc::new(Snowflake::default()),
+        }
+    }
+
+    /// Use the given clock for the timestamps of models saved with this database, instead of the system time
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Use the given node (0 to 1023) in the snowflake ids generated with this database, defaults to 0.
+    /// Every process generating snowflake ids for the same collection must use a different node, see [`generate`](crate::generate)
+    pub fn with_snowflake_node(mut self, node: u16) -> Self {
+        self.snowflake = Arc::new(Snowflake::new(node));
+        self
+    }
+
+    /// The current time, according to the clock of this database
+    pub fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
 }
 
 #[cfg(feature = "mongodb")]
@@ -13,13 +47,13 @@ where
     T: Into<mongodb::Database>,
 {
     pub fn new(db: T) -> Db<mongodb::Database> {
-        Db { inner: db.into() }
+        Db::from_backend(db.into())
     }
 }
 
 #[cfg(feature = "mongodb")]
 impl From<mongodb::Database> for Db<mongodb::Database> {
     fn from(db: mongodb::Database) -> Self {
-        Db { inner: db }
+        Db::from_backend(db)
     }
 }
@@ -23,6 +23,47 @@ pub enum MustyError {
     #[error("Model requires an ObjectID for this operation")]
     MongoModelIdRequiredForOperation,
 
+    #[cfg(feature = "mongodb")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mongodb")))]
+    #[error("Migrations are locked: another migrator is running")]
+    MigrationLocked,
+
+    #[error("Model requires an ID for this operation")]
+    ModelIdRequiredForOperation,
+
+    #[error("Version conflict: the model has been modified since it was loaded")]
+    VersionConflict,
+
+    #[error("Version out of range: the version does not fit in the version field of the model")]
+    VersionOutOfRange,
+
+    /// A model of another variant of the enum model is stored with the same id, in the collection the variants share
+    #[error("Id conflict: a model of another variant is stored with this id")]
+    IdConflict,
+
+    /// A value of a [`Filter`](crate::filter::Filter) could not be serialized, or translated into the filter of a backend
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    /// A value of an [`Update`](crate::update::Update) could not be serialized, or translated into the update of a backend
+    #[error("Invalid update: {0}")]
+    InvalidUpdate(String),
+
+    #[cfg(feature = "graphql")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "graphql")))]
+    #[error("Invalid pagination: {0}")]
+    InvalidPagination(&'static str),
+
+    /// The error of a batch of loads, shared by every load of the batch, see [`ModelLoader`](crate::loader::ModelLoader)
+    #[error(transparent)]
+    Loader(std::sync::Arc<MustyError>),
+
+    #[error(transparent)]
+    Validation(#[from] crate::validation::ValidationErrors),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
     #[cfg(feature = "bson")]
     #[cfg_attr(docsrs, doc(cfg(any(feature = "bson", feature = "mongodb"))))]
     #[error(transparent)]
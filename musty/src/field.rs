@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::{fmt::Display, marker::PhantomData};
+
+use crate::{
+    filter::Filter,
+    id::{Id, IdGuard},
+    query::Sort,
+    update::Update,
+    Model,
+};
+
+/// A typed path to a field of a model, as it is stored in the database.
+///
+/// The [`model`](crate::prelude::model) macro generates one for every stored field, available through a `fields()` function on the model:
+/// ```ignore
+/// let adults = filter!(User, age > 18);
+/// // is equivalent to
+/// let adults = User::fields().age.gt(18);
+/// ```
+/// The path respects `#[musty(rename)]` and the `_id` mapping, so renaming a field can not silently break a query.
+/// Wherever a field name is expected, a `Field` can be used instead (ex: `doc! { User::fields().name: 1 }`).
+pub struct Field<M: Model, T> {
+    path: &'static str,
+    _marker: PhantomData<fn() -> (M, T)>,
+}
+
+impl<M: Model, T> Field<M, T> {
+    pub const fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The name of the field in the database
+    pub const fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// Sort by this field in ascending order
+    pub fn asc(&self) -> Sort {
+        Sort::asc(self.path)
+    }
+
+    /// Sort by this field in descending order
+    pub fn desc(&self) -> Sort {
+        Sort::desc(self.path)
+    }
+}
+
+impl<M: Model, T> Field<M, T> {
+    /// The field is equal to the value
+    pub fn eq(&self, value: impl FieldValue<T>) -> Filter {
+        Filter::eq(self.path, value)
+    }
+
+    /// The field is not equal to the value
+    pub fn ne(&self, value: impl FieldValue<T>) -> Filter {
+        Filter::ne(self.path, value)
+    }
+
+    /// The field is greater than the value
+    pub fn gt(&self, value: impl FieldValue<T>) -> Filter {
+        Filter::gt(self.path, value)
+    }
+
+    /// The field is less than the value
+    pub fn lt(&self, value: impl FieldValue<T>) -> Filter {
+        Filter::lt(self.path, value)
+    }
+
+    /// The field is equal to any of the values
+    pub fn in_<V: FieldValue<T>>(&self, values: impl IntoIterator<Item = V>) -> Filter {
+        Filter::in_(self.path, values)
+    }
+
+    /// The field exists
+    pub fn exists(&self) -> Filter {
+        Filter::exists(self.path, true)
+    }
+}
+
+impl<M: Model, T> Field<M, T> {
+    /// Set the field to the value
+    pub fn set(&self, value: impl FieldValue<T>) -> Update {
+        Update::set(self.path, value)
+    }
+
+    /// Increment the field by the value
+    pub fn inc(&self, value: impl FieldValue<T>) -> Update {
+        Update::inc(self.path, value)
+    }
+}
+
+impl<M: Model, T> Field<M, Option<T>> {
+    /// Remove the field, only optional fields can be removed
+    pub fn unset(&self) -> Update {
+        Update::unset(self.path)
+    }
+}
+
+impl<M: Model, T> Clone for Field<M, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: Model, T> Copy for Field<M, T> {}
+
+impl<M: Model, T> std::fmt::Debug for Field<M, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Field").field(&self.path).finish()
+    }
+}
+
+impl<M: Model, T> Display for Field<M, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.path)
+    }
+}
+
+impl<M: Model, T> AsRef<str> for Field<M, T> {
+    fn as_ref(&self) -> &str {
+        self.path
+    }
+}
+
+impl<M: Model, T> From<Field<M, T>> for String {
+    fn from(field: Field<M, T>) -> Self {
+        field.path.to_string()
+    }
+}
+
+/// A value which can be compared against a field of type `T`.
+///
+/// Besides `T` itself, this allows `&str` for `String` fields, the inner id type for `Id` fields,
+/// and the inner type for `Option` fields, all of which serialize the same way as the field does.
+pub trait FieldValue<T>: Serialize {}
+
+impl<T: Serialize> FieldValue<T> for T {}
+
+impl FieldValue<String> for &str {}
+
+impl FieldValue<Option<String>> for &str {}
+
+impl<T: Serialize> FieldValue<Option<T>> for T {}
+
+impl<M: Model, I: IdGuard> FieldValue<Id<M, I>> for I {}
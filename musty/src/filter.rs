@@ -0,0 +1,273 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// A database-agnostic filter for models.
+///
+/// Filters are usually built with the [`filter!`](crate::prelude::filter) macro, which checks the field names against the model at compile time:
+/// ```ignore
+/// let filter = filter!(User, name == "jonah" && (age > 18 || !exists(email)));
+/// ```
+/// Each [`Backend`](crate::prelude::Backend) translates a `Filter` into its native filter type,
+/// so a `Filter` can be passed to any operation that takes a filter (ex: [`Model::find_one`](crate::prelude::Model::find_one)).
+///
+/// Values are held in their serialized form (see [`to_value`]), and fields are referred to by the name they are stored with in the database.
+/// A value which can not be serialized makes the filter [`Filter::Invalid`], and the operations it is passed to fail with [`MustyError::InvalidFilter`](crate::MustyError::InvalidFilter).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// The field is equal to the value
+    Eq(String, Value),
+    /// The field is not equal to the value
+    Ne(String, Value),
+    /// The field is greater than the value
+    Gt(String, Value),
+    /// The field is less than the value
+    Lt(String, Value),
+    /// The field is equal to any of the values
+    In(String, Vec<Value>),
+    /// The field exists (or does not exist, if `false`)
+    Exists(String, bool),
+    /// All of the filters match
+    And(Vec<Filter>),
+    /// Any of the filters match
+    Or(Vec<Filter>),
+    /// The filter does not match
+    Not(Box<Filter>),
+    /// A value of the filter could not be serialized, with the serialization error
+    Invalid(String),
+}
+
+impl Filter {
+    /// The field is equal to the value
+    pub fn eq<V: Serialize>(field: impl Into<String>, value: V) -> Self {
+        match to_value(value) {
+            Ok(value) => Self::Eq(field.into(), value),
+            Err(err) => Self::Invalid(err.to_string()),
+        }
+    }
+
+    /// The field is not equal to the value
+    pub fn ne<V: Serialize>(field: impl Into<String>, value: V) -> Self {
+        match to_value(value) {
+            Ok(value) => Self::Ne(field.into(), value),
+            Err(err) => Self::Invalid(err.to_string()),
+        }
+    }
+
+    /// The field is greater than the value
+    pub fn gt<V: Serialize>(field: impl Into<String>, value: V) -> Self {
+        match to_value(value) {
+            Ok(value) => Self::Gt(field.into(), value),
+            Err(err) => Self::Invalid(err.to_string()),
+        }
+    }
+
+    /// The field is less than the value
+    pub fn lt<V: Serialize>(field: impl Into<String>, value: V) -> Self {
+        match to_value(value) {
+            Ok(value) => Self::Lt(field.into(), value),
+            Err(err) => Self::Invalid(err.to_string()),
+        }
+    }
+
+    /// The field is equal to any of the values
+    pub fn in_<V, I>(field: impl Into<String>, values: I) -> Self
+    where
+        V: Serialize,
+        I: IntoIterator<Item = V>,
+    {
+        match values.into_iter().map(to_value).collect() {
+            Ok(values) => Self::In(field.into(), values),
+            Err(err) => Self::Invalid(err.to_string()),
+        }
+    }
+
+    /// The field exists (or does not exist, if `exists` is `false`)
+    pub fn exists(field: impl Into<String>, exists: bool) -> Self {
+        Self::Exists(field.into(), exists)
+    }
+
+    /// Both this filter and `other` match
+    pub fn and(self, other: Filter) -> Self {
+        match self {
+            Self::And(mut filters) => {
+                filters.push(other);
+                Self::And(filters)
+            }
+            filter => Self::And(vec![filter, other]),
+        }
+    }
+
+    /// Either this filter or `other` matches
+    pub fn or(self, other: Filter) -> Self {
+        match self {
+            Self::Or(mut filters) => {
+                filters.push(other);
+                Self::Or(filters)
+            }
+            filter => Self::Or(vec![filter, other]),
+        }
+    }
+
+    /// The serialization error of the first invalid value of this filter, if any
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Self::Invalid(err) => Some(err),
+            Self::And(filters) | Self::Or(filters) => filters.iter().find_map(Filter::error),
+            Self::Not(filter) => filter.error(),
+            _ => None,
+        }
+    }
+
+    /// The same filter on the fields of an embedded document (ex: `fullDocument` in MongoDB change events)
+    #[cfg(feature = "mongodb")]
+    pub(crate) fn nested(self, prefix: &str) -> Self {
+        let path = |field: String| format!("{}.{}", prefix, field);
+        match self {
+            Self::Eq(field, value) => Self::Eq(path(field), value),
+            Self::Ne(field, value) => Self::Ne(path(field), value),
+            Self::Gt(field, value) => Self::Gt(path(field), value),
+            Self::Lt(field, value) => Self::Lt(path(field), value),
+            Self::In(field, values) => Self::In(path(field), values),
+            Self::Exists(field, exists) => Self::Exists(path(field), exists),
+            Self::And(filters) => Self::And(
+                filters
+                    .into_iter()
+                    .map(|filter| filter.nested(prefix))
+                    .collect(),
+            ),
+            Self::Or(filters) => Self::Or(
+                filters
+                    .into_iter()
+                    .map(|filter| filter.nested(prefix))
+                    .collect(),
+            ),
+            Self::Not(filter) => Self::Not(Box::new(filter.nested(prefix))),
+            Self::Invalid(err) => Self::Invalid(err),
+        }
+    }
+}
+
+impl std::ops::Not for Filter {
+    type Output = Filter;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Not(filter) => *filter,
+            filter => Self::Not(Box::new(filter)),
+        }
+    }
+}
+
+/// Serializes a value of a filter, an update or a cursor to JSON.
+///
+/// UUIDs are serialized as strings in JSON but as binary (subtype 4) in BSON, so with the `bson` feature
+/// they are held as `{"$uuid": "..."}` extended JSON, which MongoDB filters translate back to binary
+pub(crate) fn to_value<V: Serialize>(value: V) -> serde_json::Result<Value> {
+    #[cfg_attr(not(feature = "bson"), allow(unused_mut))]
+    let mut json = serde_json::to_value(&value)?;
+    #[cfg(feature = "bson")]
+    if let Ok(bson) = bson::to_bson(&value) {
+        mark_uuids(&mut json, &bson);
+    }
+    Ok(json)
+}
+
+/// Replaces the strings of `json` which are UUIDs in `bson`, the same value serialized to BSON
+#[cfg(feature = "bson")]
+fn mark_uuids(json: &mut Value, bson: &bson::Bson) {
+    use bson::{spec::BinarySubtype, Bson};
+
+    match (json, bson) {
+        (json @ Value::String(_), Bson::Binary(binary))
+            if binary.subtype == BinarySubtype::Uuid =>
+        {
+            *json = serde_json::json!({ "$uuid": json.take() });
+        }
+        (Value::Array(values), Bson::Array(bsons)) => {
+            for (value, bson) in values.iter_mut().zip(bsons) {
+                mark_uuids(value, bson);
+            }
+        }
+        (Value::Object(fields), Bson::Document(document)) => {
+            for (field, value) in fields.iter_mut() {
+                if let Some(bson) = document.get(field) {
+                    mark_uuids(value, bson);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The value held by a filter or a cursor, without the `{"$uuid": "..."}` marking of UUIDs (see [`to_value`])
+#[cfg(feature = "memory")]
+pub(crate) fn unmarked(value: &Value) -> &Value {
+    match value {
+        Value::Object(fields) if fields.len() == 1 => fields.get("$uuid").unwrap_or(value),
+        value => value,
+    }
+}
+
+/// Looks up a (possibly dotted) field path in a serialized model
+#[cfg(any(feature = "memory", feature = "graphql"))]
+pub(crate) fn lookup<'a>(document: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(document, |value, segment| value.get(segment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn combine() {
+        let filter = Filter::eq("name", "jonah")
+            .and(Filter::gt("age", 18))
+            .and(!Filter::exists("email", true));
+
+        assert_eq!(
+            filter,
+            Filter::And(vec![
+                Filter::Eq("name".to_string(), json!("jonah")),
+                Filter::Gt("age".to_string(), json!(18)),
+                Filter::Not(Box::new(Filter::Exists("email".to_string(), true))),
+            ])
+        );
+    }
+
+    #[cfg(feature = "mongodb")]
+    #[test]
+    fn nested() {
+        let filter = Filter::eq("name", "jonah").or(!Filter::exists("email", true));
+        assert_eq!(
+            filter.nested("fullDocument"),
+            Filter::Or(vec![
+                Filter::Eq("fullDocument.name".to_string(), json!("jonah")),
+                Filter::Not(Box::new(Filter::Exists(
+                    "fullDocument.email".to_string(),
+                    true
+                ))),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        // maps with non-string keys can not be serialized to JSON
+        let tags = HashMap::from([((1, 2), "a")]);
+        assert!(matches!(Filter::eq("tags", &tags), Filter::Invalid(_)));
+        assert!(matches!(Filter::in_("tags", [&tags]), Filter::Invalid(_)));
+
+        let filter = Filter::eq("name", "jonah").and(!Filter::eq("tags", &tags));
+        assert!(filter.error().is_some());
+        assert_eq!(Filter::eq("name", "jonah").error(), None);
+    }
+
+    #[test]
+    fn double_negation() {
+        let filter = Filter::eq("name", "jonah");
+        assert_eq!(!!filter.clone(), filter);
+    }
+}
@@ -0,0 +1,256 @@
+//! Client-side generation of model ids, used by models with `#[musty(id(generate = "..."))]` on their id field.
+//!
+//! The id is generated when a model without an id is saved, before it is written, so every backend stores the same id:
+//! ```ignore
+//! #[model(mongo(collection = "orders"))]
+//! struct Order {
+//!     #[musty(id(generate = "uuid_v7"))]
+//!     id: Uuid,
+//!     total: u32,
+//! }
+//! ```
+//! The strategies are:
+//! - `uuid_v4`: a random [`Uuid`]
+//! - `uuid_v7`: a time-ordered [`Uuid`], using the clock of the database
+//! - `ulid`: a time-ordered [ULID](https://github.com/ulid/spec), as a `String`
+//! - `snowflake`: a time-ordered `i64`, unique per node (see [`Musty::with_snowflake_node`](crate::Musty::with_snowflake_node))
+//! - `sequence`: the next `i64` of an atomic counter kept per collection by the database, starting at 1
+
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{db::Db, prelude::Backend, prelude::Context, Model, Result};
+
+/// A UUID id, stored as BSON binary (subtype 4) by MongoDB, and as a hyphenated string by other backends
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid(uuid::Uuid);
+
+impl Uuid {
+    /// A random (version 4) UUID
+    pub fn new_v4() -> Self {
+        Self(uuid::Uuid::new_v4())
+    }
+
+    /// A time-ordered (version 7) UUID, for the given time
+    pub fn new_v7(now: SystemTime) -> Self {
+        let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let timestamp = uuid::Timestamp::from_unix(
+            uuid::NoContext,
+            since_epoch.as_secs(),
+            since_epoch.subsec_nanos(),
+        );
+        Self(uuid::Uuid::new_v7(timestamp))
+    }
+
+    pub fn as_uuid(&self) -> &uuid::Uuid {
+        &self.0
+    }
+}
+
+impl From<uuid::Uuid> for Uuid {
+    fn from(uuid: uuid::Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl From<Uuid> for uuid::Uuid {
+    fn from(uuid: Uuid) -> Self {
+        uuid.0
+    }
+}
+
+impl Display for Uuid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        uuid::Uuid::parse_str(s).map(Self)
+    }
+}
+
+/// Serialized through `bson::Uuid`, which the BSON serializer writes as binary subtype 4,
+/// and other serializers write as the inner UUID
+#[cfg(feature = "bson")]
+impl Serialize for Uuid {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        bson::Uuid::from(self.0).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "bson")]
+impl<'de> Deserialize<'de> for Uuid {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Self(bson::Uuid::deserialize(deserializer)?.into()))
+    }
+}
+
+#[cfg(not(feature = "bson"))]
+impl Serialize for Uuid {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(not(feature = "bson"))]
+impl<'de> Deserialize<'de> for Uuid {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        uuid::Uuid::deserialize(deserializer).map(Self)
+    }
+}
+
+/// A random (version 4) UUID
+pub fn uuid_v4() -> Uuid {
+    Uuid::new_v4()
+}
+
+/// A time-ordered (version 7) UUID, using the clock of the database
+pub fn uuid_v7<B: Backend>(db: &Db<B>) -> Uuid {
+    Uuid::new_v7(db.now())
+}
+
+/// A time-ordered ULID, using the clock of the database
+pub fn ulid<B: Backend>(db: &Db<B>) -> String {
+    ulid::Ulid::from_datetime(db.now()).to_string()
+}
+
+/// A snowflake id, using the clock and the snowflake node of the database
+pub fn snowflake<B: Backend>(db: &Db<B>) -> i64 {
+    db.snowflake.next(db.now())
+}
+
+/// The next value of the sequence of the collection of `M`, from an atomic counter kept by the database
+pub async fn sequence<M, B>(db: &Db<B>) -> Result<i64>
+where
+    M: Model + Context<M::Id, B> + 'static,
+    B: Backend,
+{
+    db.inner.next_sequence::<M, M::Id>().await
+}
+
+/// Generates snowflake ids: 41 bits of milliseconds since 2020-01-01, 10 bits of node, and 12 bits of sequence.
+///
+/// The time never goes backwards: if the clock does, or if the 4096 ids of a millisecond are used up,
+/// ids are generated for the last used millisecond (or the next one), instead of waiting for the clock
+pub(crate) struct Snowflake {
+    node: u16,
+    /// the last used millisecond, and the last used sequence within it
+    state: Mutex<(u64, u64)>,
+}
+
+impl Snowflake {
+    /// The epoch of snowflake ids, 2020-01-01T00:00:00Z
+    const EPOCH: Duration = Duration::from_millis(1_577_836_800_000);
+    const NODE_BITS: u32 = 10;
+    const SEQUENCE_BITS: u32 = 12;
+    const MAX_NODE: u16 = (1 << Self::NODE_BITS) - 1;
+
+    pub(crate) fn new(node: u16) -> Self {
+        Self {
+            node: node & Self::MAX_NODE,
+            state: Mutex::new((0, 0)),
+        }
+    }
+
+    fn next(&self, now: SystemTime) -> i64 {
+        let millis = now
+            .duration_since(UNIX_EPOCH + Self::EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (last, sequence) = *state;
+        *state = match millis > last {
+            true => (millis, 0),
+            false if sequence < (1 << Self::SEQUENCE_BITS) - 1 => (last, sequence + 1),
+            false => (last + 1, 0),
+        };
+
+        let (millis, sequence) = *state;
+        ((millis << (Self::NODE_BITS + Self::SEQUENCE_BITS))
+            | ((self.node as u64) << Self::SEQUENCE_BITS)
+            | sequence) as i64
+    }
+}
+
+impl Default for Snowflake {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{Snowflake, Uuid};
+
+    #[test]
+    fn snowflakes_are_ordered() {
+        let snowflake = Snowflake::new(3);
+        let now = UNIX_EPOCH + Snowflake::EPOCH + Duration::from_millis(10);
+
+        let first = snowflake.next(now);
+        assert_eq!(first, (10 << 22) | (3 << 12));
+        assert_eq!(snowflake.next(now), first + 1);
+
+        // the clock going backwards does not reuse ids
+        let earlier = snowflake.next(now - Duration::from_millis(5));
+        assert!(earlier > first + 1);
+
+        let later = snowflake.next(now + Duration::from_millis(1));
+        assert!(later > earlier);
+    }
+
+    #[test]
+    fn uuids() {
+        let now = SystemTime::now();
+        let first = Uuid::new_v7(now);
+        let second = Uuid::new_v7(now + Duration::from_millis(1));
+        assert!(first < second);
+        assert_eq!(first.as_uuid().get_version_num(), 7);
+        assert_eq!(Uuid::new_v4().as_uuid().get_version_num(), 4);
+        assert_eq!(first.to_string().parse::<Uuid>().unwrap(), first);
+
+        let json = serde_json::to_value(first).unwrap();
+        assert_eq!(json, serde_json::Value::String(first.to_string()));
+        assert_eq!(serde_json::from_value::<Uuid>(json).unwrap(), first);
+    }
+
+    #[cfg(feature = "bson")]
+    #[test]
+    fn uuids_are_stored_as_binary() {
+        let uuid = Uuid::new_v4();
+        let stored = bson::to_bson(&uuid).unwrap();
+        assert!(matches!(
+            &stored,
+            bson::Bson::Binary(binary) if binary.subtype == bson::spec::BinarySubtype::Uuid
+        ));
+        assert_eq!(bson::from_bson::<Uuid>(stored).unwrap(), uuid);
+    }
+}
@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+
+use crate::{
+    prelude::{Backend, Model, Musty},
+    Result,
+};
+
+/// Lifecycle hooks for a model, run by the generic [`Model`] operations.
+///
+/// Implement this trait and opt in with `#[model(hooks)]` on the model struct, all hooks default to doing nothing:
+/// ```ignore
+/// #[model(mongo(), hooks)]
+/// struct User {
+///     id: ObjectId,
+///     email: String,
+/// }
+///
+/// #[async_trait]
+/// impl ModelHooks for User {
+///     async fn before_save<B: Backend>(&mut self, _db: &Musty<B>) -> musty::Result<()> {
+///         self.email = self.email.trim().to_lowercase();
+///         Ok(())
+///     }
+/// }
+/// ```
+/// An error returned by a `before_*` hook aborts the operation.
+/// Soft-deleting a model only updates its soft delete marker, so the delete hooks run but the save hooks do not.
+/// Restoring a model runs no hooks: it is neither saved nor deleted, and there are no restore hooks.
+#[async_trait]
+pub trait ModelHooks: Model {
+    /// Runs before the model is saved, and before its timestamps are filled in
+    async fn before_save<B: Backend>(&mut self, _db: &Musty<B>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs after the model has been saved
+    async fn after_save<B: Backend>(&mut self, _db: &Musty<B>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs before the model is deleted
+    async fn before_delete<B: Backend>(&mut self, _db: &Musty<B>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs after the model has been deleted
+    async fn after_delete<B: Backend>(&mut self, _db: &Musty<B>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs after the model has been loaded from the database, before it is returned
+    async fn after_load<B: Backend>(&mut self, _db: &Musty<B>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A lifecycle hook of a model, see [`ModelHooks`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hook {
+    BeforeSave,
+    AfterSave,
+    BeforeDelete,
+    AfterDelete,
+    AfterLoad,
+}
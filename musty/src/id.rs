@@ -41,6 +41,14 @@ impl<M: Model, I: IdGuard> Id<M, I> {
     pub fn is_none(&self) -> bool {
         self.inner.is_none()
     }
+
+    /// The same id, for another model stored in the same collection (ex: an enum model and its variants)
+    pub fn cast<N: Model>(self) -> Id<N, I> {
+        Id {
+            inner: self.inner,
+            _marker: PhantomData,
+        }
+    }
 }
 
 impl<M: Model, I: IdGuard> Clone for Id<M, I> {
@@ -76,10 +84,10 @@ where
     I: GeneratedIdGuard,
 {
     pub fn none() -> Self {
-        return Self {
+        Self {
             inner: None,
             _marker: PhantomData,
-        };
+        }
     }
 }
 
@@ -140,7 +148,7 @@ mod bson {
 
         fn try_from(id: Id<M, I>) -> Result<Self, Self::Error> {
             match id.inner {
-                Some(id) => Ok(ObjectId::parse_str(&id.to_string())?),
+                Some(id) => Ok(ObjectId::parse_str(id.to_string())?),
                 None => Err(crate::error::MustyError::Other(anyhow::anyhow!(
                     "Id is None"
                 ))),
@@ -153,7 +161,7 @@ mod bson {
 
         fn try_from(id: &Id<M, I>) -> Result<Self, Self::Error> {
             match &id.inner {
-                Some(id) => Ok(ObjectId::parse_str(&id.to_string())?),
+                Some(id) => Ok(ObjectId::parse_str(id.to_string())?),
                 None => Err(crate::error::MustyError::Other(anyhow::anyhow!(
                     "Id is None"
                 ))),
@@ -1,19 +1,41 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![doc = include_str!("../README.md")]
 
-mod backend;
+pub mod backend;
+mod clock;
+#[cfg(feature = "graphql")]
+#[cfg_attr(docsrs, doc(cfg(feature = "graphql")))]
+pub mod connection;
 mod context;
 mod cursor;
 mod db;
 mod error;
+mod field;
+mod hooks;
+pub mod filter;
+pub mod generate;
 mod id;
+pub mod loader;
+#[cfg(feature = "mongodb")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mongodb")))]
+pub mod migrate;
 mod model;
+mod query;
+mod reference;
+#[cfg(all(feature = "graphql", feature = "mongodb"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "graphql", feature = "mongodb"))))]
+pub mod subscription;
+pub mod update;
+pub mod validation;
 
 #[cfg(feature = "bson")]
 pub use bson;
 /// Re-exports
 #[cfg(feature = "mongodb")]
 pub use mongodb;
+#[cfg(feature = "mongodb")]
+#[doc(hidden)]
+pub use inventory;
 
 /// Result type used by musty.
 pub type Result<T> = std::result::Result<T, error::MustyError>;
@@ -22,27 +44,53 @@ pub use error::MustyError;
 
 #[cfg(feature = "mongodb")]
 #[cfg_attr(docsrs, doc(cfg(feature = "mongodb")))]
-pub use backend::MongoModel;
-pub use model::Model;
+pub use backend::{
+    Aggregate, AggregateCursor, ChangeEvent, GroupId, IndexDrift, IndexSyncOptions,
+    IndexSyncReport, MongoChangeStream, MongoCursor, MongoFilter, MongoModel,
+};
+pub use model::{Model, SaveOutcome};
 
 pub use crate::db::Db as Musty;
 
 /// Exports needed to use musty.
 pub mod prelude {
     pub use crate::backend::Backend;
+    pub use crate::clock::{Clock, FixedClock, SystemClock, Timestamp};
     pub use crate::context::Context;
+    pub use crate::cursor::MustyCursor;
     pub use crate::db::Db as Musty;
     pub use crate::error::MustyError;
+    pub use crate::field::{Field, FieldValue};
+    pub use crate::hooks::{Hook, ModelHooks};
+    pub use crate::filter::Filter;
     pub use crate::id::DefaultType as DefaultIdType;
     pub use crate::id::GeneratedIdGuard;
     pub use crate::id::Id;
     pub use crate::id::IdGuard;
-    pub use crate::model::Model;
+    pub use crate::loader::ModelLoader;
+    pub use crate::model::{Model, SaveOutcome};
+    pub use crate::query::{DeletedScope, QueryOptions, Sort, SortOrder};
+    pub use crate::reference::{populate, Ref};
+    pub use crate::update::Update;
     #[doc(hidden)]
     pub use async_trait::async_trait;
     pub use musty_proc_macro::*;
 
     #[cfg(feature = "mongodb")]
     #[cfg_attr(docsrs, doc(cfg(feature = "mongodb")))]
-    pub use crate::backend::MongoModel;
+    pub use crate::backend::{
+        ChangeEvent, IndexSyncOptions, MongoChangeStream, MongoCursor, MongoModel,
+    };
+
+    #[cfg(feature = "bson")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "bson")))]
+    pub use crate::clock::BsonTimestamp;
+
+    #[cfg(feature = "memory")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "memory")))]
+    pub use crate::backend::memory::MemoryBackend;
+
+    #[cfg(feature = "graphql")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "graphql")))]
+    pub use crate::connection::ConnectionQuery;
 }
@@ -0,0 +1,220 @@
+//! Batched loading of models by id, for request-scoped loads like the resolvers of a GraphQL query.
+//!
+//! A [`ModelLoader`] coalesces the loads started in the same tick (ex: the author of every post of a page, resolved concurrently)
+//! into one query with [`Model::get_by_ids`], instead of one query per model.
+//! Loaded models are cached by the loader, so create one loader per request:
+//! ```ignore
+//! let request = async_graphql::Request::new(query).data(ModelLoader::<User, _>::new(&db));
+//!
+//! #[Object]
+//! impl Post {
+//!     async fn author(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<User>> {
+//!         let loader = ctx.data_unchecked::<ModelLoader<User, mongodb::Database>>();
+//!         Ok(loader.load(self.author.clone()).await?)
+//!     }
+//! }
+//! ```
+//! The loader does not spawn tasks, so it works with any async runtime: the first load of a batch yields once,
+//! letting the other loads polled with it (ex: by `join_all`) join the batch, then it queries the batch for all of them.
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context as TaskContext, Poll},
+};
+
+use futures::{channel::oneshot, future::try_join_all};
+
+use crate::{
+    db::Db,
+    id::Id,
+    prelude::{Backend, Context},
+    reference::key,
+    Model, MustyError, Result,
+};
+
+/// The models loaded by a batch by id, or the error of its query
+type BatchResult<M> = std::result::Result<Arc<HashMap<String, M>>, Arc<MustyError>>;
+
+/// Loads models of type `M` by id, batching the loads of the same tick into one query and caching the loaded models.
+/// Soft-deleted models are not found, like with [`Model::get_by_id`]
+pub struct ModelLoader<M: Model, B: Backend> {
+    db: Db<B>,
+    state: Mutex<LoaderState<M>>,
+}
+
+struct LoaderState<M: Model> {
+    /// the loaded models by id, `None` for ids which were not found
+    loaded: HashMap<String, Option<M>>,
+    /// the batch which loads can still join, until it is queried
+    batch: Option<Batch<M>>,
+}
+
+/// The ids of a batch, and the loads waiting for the first load of the batch to query them
+struct Batch<M: Model> {
+    ids: Vec<Id<M, M::Id>>,
+    waiters: Vec<oneshot::Sender<BatchResult<M>>>,
+}
+
+impl<M, B> ModelLoader<M, B>
+where
+    M: Model + Context<M::Id, B> + Clone + 'static,
+    B: Backend,
+{
+    pub fn new(db: &Db<B>) -> Self {
+        Self {
+            db: db.clone(),
+            state: Mutex::new(LoaderState {
+                loaded: HashMap::new(),
+                batch: None,
+            }),
+        }
+    }
+
+    /// Load the model with the given id, in the same query as the other loads of this tick.
+    /// Returns `None` if the model does not exist, or if the id is not set
+    pub async fn load(&self, id: impl Into<Id<M, M::Id>>) -> Result<Option<M>> {
+        let id = id.into();
+        let key = match key(&id) {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+
+        loop {
+            let waiter = {
+                let mut state = self.lock();
+                if let Some(model) = state.loaded.get(&key) {
+                    return Ok(model.clone());
+                }
+                match state.batch.as_mut() {
+                    Some(batch) => {
+                        let (sender, receiver) = oneshot::channel();
+                        batch.ids.push(id.clone());
+                        batch.waiters.push(sender);
+                        Some(receiver)
+                    }
+                    None => {
+                        state.batch = Some(Batch {
+                            ids: vec![id.clone()],
+                            waiters: Vec::new(),
+                        });
+                        None
+                    }
+                }
+            };
+
+            let models = match waiter {
+                None => self.load_batch().await,
+                Some(receiver) => match receiver.await {
+                    Ok(models) => models,
+                    // the first load of the batch was dropped before querying it, load the id again
+                    Err(oneshot::Canceled) => continue,
+                },
+            };
+            return Ok(models.map_err(MustyError::Loader)?.get(&key).cloned());
+        }
+    }
+
+    /// Load the models with the given ids in one query, in the order of the ids.
+    /// Ids which are not found are skipped
+    pub async fn load_many(&self, ids: impl IntoIterator<Item = Id<M, M::Id>>) -> Result<Vec<M>> {
+        let models = try_join_all(ids.into_iter().map(|id| self.load(id))).await?;
+        Ok(models.into_iter().flatten().collect())
+    }
+
+    /// Add a model to the cache of the loader, so loading it does not query the database
+    pub fn prime(&self, model: M) {
+        if let Some(key) = key(model.id()) {
+            self.lock().loaded.insert(key, Some(model));
+        }
+    }
+
+    /// Clear the cache of the loader, so models are loaded from the database again
+    pub fn clear(&self) {
+        self.lock().loaded.clear();
+    }
+
+    /// Run by the first load of a batch: once the other loads of this tick have joined the batch, it is closed and its ids are loaded.
+    /// If this load is dropped before closing the batch, the batch is dropped, and its other loads start a new one
+    async fn load_batch(&self) -> BatchResult<M> {
+        let mut open = OpenBatch(Some(self));
+        YieldNow(false).await;
+        let batch = open.close();
+
+        let result = match M::get_by_ids(&self.db, &batch.ids).await {
+            Ok(models) => Ok(Arc::new(
+                models
+                    .into_iter()
+                    .filter_map(|model| Some((key(model.id())?, model)))
+                    .collect::<HashMap<_, _>>(),
+            )),
+            Err(err) => Err(Arc::new(err)),
+        };
+
+        if let Ok(models) = &result {
+            let mut state = self.lock();
+            for key in batch.ids.iter().filter_map(key) {
+                let model = models.get(&key).cloned();
+                state.loaded.insert(key, model);
+            }
+        }
+        for waiter in batch.waiters {
+            let _ = waiter.send(result.clone());
+        }
+        result
+    }
+
+    fn lock(&self) -> MutexGuard<'_, LoaderState<M>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// The open batch of a loader, dropped with its first load unless it is closed
+struct OpenBatch<'a, M, B>(Option<&'a ModelLoader<M, B>>)
+where
+    M: Model + Context<M::Id, B> + Clone + 'static,
+    B: Backend;
+
+impl<'a, M, B> OpenBatch<'a, M, B>
+where
+    M: Model + Context<M::Id, B> + Clone + 'static,
+    B: Backend,
+{
+    fn close(&mut self) -> Batch<M> {
+        let loader = self.0.take().expect("batch is already closed");
+        let batch = loader.lock().batch.take();
+        batch.expect("open batch of the loader")
+    }
+}
+
+impl<'a, M, B> Drop for OpenBatch<'a, M, B>
+where
+    M: Model + Context<M::Id, B> + Clone + 'static,
+    B: Backend,
+{
+    fn drop(&mut self) {
+        if let Some(loader) = self.0.take() {
+            loader.lock().batch = None;
+        }
+    }
+}
+
+/// Returns `Pending` once, so the futures polled after it get to run before it completes
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
@@ -0,0 +1,391 @@
+//! Schema migrations for MongoDB, recorded in a ledger collection.
+//!
+//! Migrations are types implementing [`Migration`], registered in order on a [`Migrator`]:
+//! ```ignore
+//! struct RenameUserName;
+//!
+//! #[async_trait]
+//! impl Migration for RenameUserName {
+//!     fn version(&self) -> u64 {
+//!         1
+//!     }
+//!
+//!     async fn up(&self, db: &Musty<Database>) -> musty::Result<()> {
+//!         User::collection(db)
+//!             .update_many(doc! {}, doc! { "$rename": { "username": "name" } }, None)
+//!             .await?;
+//!         Ok(())
+//!     }
+//!
+//!     async fn down(&self, db: &Musty<Database>) -> musty::Result<()> {
+//!         User::collection(db)
+//!             .update_many(doc! {}, doc! { "$rename": { "name": "username" } }, None)
+//!             .await?;
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let report = Migrator::new().migration(RenameUserName).run(&db, None).await?;
+//! ```
+//! The migrator takes a lock in the ledger collection while it runs, so concurrent deploys do not run migrations at the same time.
+//! The lock is renewed before each migration, so a single migration must take less than the [lock timeout](Migrator::lock_timeout).
+
+use std::{collections::BTreeSet, time::Duration};
+
+use async_trait::async_trait;
+use bson::{doc, DateTime, Document};
+use futures::TryStreamExt;
+use mongodb::{error::ErrorKind, options::UpdateOptions, Collection, Database};
+
+use crate::{db::Db, error::MustyError, Result};
+
+/// A schema migration.
+#[async_trait]
+pub trait Migration: Send + Sync {
+    /// The version of this migration, migrations are applied in ascending order of their versions
+    fn version(&self) -> u64;
+
+    /// The name of this migration, recorded in the ledger
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Applies this migration
+    async fn up(&self, db: &Db<Database>) -> Result<()>;
+
+    /// Reverts this migration, migrations can not be reverted by default
+    async fn down(&self, _db: &Db<Database>) -> Result<()> {
+        Err(MustyError::Other(anyhow::anyhow!(
+            "Migration {} ({}) can not be reverted",
+            self.version(),
+            self.name()
+        )))
+    }
+}
+
+/// Options for [`Migrator::run`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MigrateOptions {
+    /// The version to migrate to: migrations up to it are applied, and applied migrations after it are reverted.
+    /// Migrates to the latest registered version if `None`
+    pub target: Option<u64>,
+    /// Only report the migrations which would be applied or reverted, without running them
+    pub dry_run: bool,
+}
+
+impl MigrateOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Migrates to the given version, `0` reverts every migration
+    pub fn target(mut self, version: u64) -> Self {
+        self.target = Some(version);
+        self
+    }
+
+    /// Only reports the migrations which would be applied or reverted
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+}
+
+/// The result of running the migrations
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The versions of the applied migrations, in the order they were applied
+    pub applied: Vec<u64>,
+    /// The versions of the reverted migrations, in the order they were reverted
+    pub reverted: Vec<u64>,
+    /// Whether the migrations were only planned, see [`MigrateOptions::dry_run`]
+    pub dry_run: bool,
+}
+
+/// Runs registered migrations against a database, and records the applied versions in a ledger collection.
+pub struct Migrator {
+    migrations: Vec<Box<dyn Migration>>,
+    ledger: String,
+    lock_timeout: Duration,
+}
+
+impl Default for Migrator {
+    fn default() -> Self {
+        Self {
+            migrations: Vec::new(),
+            ledger: String::from("musty_migrations"),
+            lock_timeout: Duration::from_secs(15 * 60),
+        }
+    }
+}
+
+impl Migrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a migration, migrations must be registered in ascending order of their versions
+    pub fn migration(mut self, migration: impl Migration + 'static) -> Self {
+        self.migrations.push(Box::new(migration));
+        self
+    }
+
+    /// The name of the ledger collection, defaults to `musty_migrations`
+    pub fn ledger(mut self, collection: impl Into<String>) -> Self {
+        self.ledger = collection.into();
+        self
+    }
+
+    /// How long a lock is held without being renewed before it is considered abandoned (ex: by a crashed deploy) and can be taken over,
+    /// defaults to 15 minutes. The lock is renewed before each migration
+    pub fn lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// The versions of the migrations which have been applied to the database
+    pub async fn applied(&self, db: &Db<Database>) -> Result<BTreeSet<u64>> {
+        let entries = self
+            .collection(db)
+            .find(doc! { "version": { "$exists": true } }, None)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        Ok(entries
+            .iter()
+            .filter_map(|entry| entry.get_i64("version").ok())
+            .map(|version| version as u64)
+            .collect())
+    }
+
+    /// Applies the pending migrations (or reverts the applied ones after the target version, see [`MigrateOptions::target`]).
+    /// Fails with [`MustyError::MigrationLocked`] if another migrator is running
+    pub async fn run(
+        &self,
+        db: &Db<Database>,
+        options: impl Into<Option<MigrateOptions>>,
+    ) -> Result<MigrationReport> {
+        let options = options.into().unwrap_or_default();
+        let versions = self
+            .migrations
+            .iter()
+            .map(|migration| migration.version())
+            .collect::<Vec<_>>();
+
+        if options.dry_run {
+            let plan = plan(&versions, &self.applied(db).await?, options.target)?;
+            return Ok(MigrationReport {
+                applied: plan.up.iter().map(|&index| versions[index]).collect(),
+                reverted: plan.down,
+                dry_run: true,
+            });
+        }
+
+        let owner = self.lock(db).await?;
+        let report = self.migrate(db, &versions, options.target, &owner).await;
+        let unlocked = self.unlock(db, &owner).await;
+        let report = report?;
+        unlocked?;
+        Ok(report)
+    }
+
+    async fn migrate(
+        &self,
+        db: &Db<Database>,
+        versions: &[u64],
+        target: Option<u64>,
+        owner: &str,
+    ) -> Result<MigrationReport> {
+        let plan = plan(versions, &self.applied(db).await?, target)?;
+        let collection = self.collection(db);
+        let mut report = MigrationReport::default();
+
+        for version in plan.down {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|migration| migration.version() == version)
+                .expect("planned migrations are registered");
+            self.renew(db, owner).await?;
+            migration.down(db).await?;
+            collection
+                .delete_one(doc! { "_id": version as i64 }, None)
+                .await?;
+            report.reverted.push(version);
+        }
+
+        for index in plan.up {
+            let migration = &self.migrations[index];
+            self.renew(db, owner).await?;
+            migration.up(db).await?;
+            collection
+                .insert_one(
+                    doc! {
+                        "_id": migration.version() as i64,
+                        "version": migration.version() as i64,
+                        "name": migration.name(),
+                        "applied_at": DateTime::from_system_time(db.now()),
+                    },
+                    None,
+                )
+                .await?;
+            report.applied.push(migration.version());
+        }
+
+        Ok(report)
+    }
+
+    /// Takes the lock document of the ledger if it is unlocked or abandoned, and returns the random token identifying this migrator as its owner.
+    /// If it is held, the upsert tries to insert a second lock document, which fails with a duplicate key error
+    async fn lock(&self, db: &Db<Database>) -> Result<String> {
+        let now = db.now();
+        // a timeout going back before the earliest datetime never abandons a lock
+        let abandoned = now
+            .checked_sub(self.lock_timeout)
+            .map_or(DateTime::MIN, DateTime::from_system_time);
+        let owner = uuid::Uuid::new_v4().to_string();
+        let result = self
+            .collection(db)
+            .update_one(
+                doc! {
+                    "_id": "lock",
+                    "$or": [{ "locked": false }, { "locked_at": { "$lt": abandoned } }],
+                },
+                doc! {
+                    "$set": {
+                        "locked": true,
+                        "locked_at": DateTime::from_system_time(now),
+                        "owner": &owner,
+                    }
+                },
+                UpdateOptions::builder().upsert(true).build(),
+            )
+            .await;
+
+        match result {
+            Ok(_) => Ok(owner),
+            Err(err) if is_duplicate_key(&err) => Err(MustyError::MigrationLocked),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Extends the lease of the lock held by `owner`.
+    /// Fails with [`MustyError::MigrationLocked`] if the lock has been taken over since, after it was held for longer than the lock timeout
+    async fn renew(&self, db: &Db<Database>, owner: &str) -> Result<()> {
+        let result = self
+            .collection(db)
+            .update_one(
+                doc! { "_id": "lock", "locked": true, "owner": owner },
+                doc! { "$set": { "locked_at": DateTime::from_system_time(db.now()) } },
+                None,
+            )
+            .await?;
+        match result.matched_count {
+            0 => Err(MustyError::MigrationLocked),
+            _ => Ok(()),
+        }
+    }
+
+    /// Releases the lock, unless it has been taken over by another migrator
+    async fn unlock(&self, db: &Db<Database>, owner: &str) -> Result<()> {
+        self.collection(db)
+            .update_one(
+                doc! { "_id": "lock", "owner": owner },
+                doc! { "$set": { "locked": false } },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    fn collection(&self, db: &Db<Database>) -> Collection<Document> {
+        db.inner.collection(&self.ledger)
+    }
+}
+
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Write(mongodb::error::WriteFailure::WriteError(err)) => err.code == 11000,
+        ErrorKind::Command(err) => err.code == 11000,
+        _ => false,
+    }
+}
+
+/// The migrations to run: the indexes of the registered migrations to apply in order, and the versions to revert in order
+#[derive(Debug, PartialEq, Eq)]
+struct Plan {
+    up: Vec<usize>,
+    down: Vec<u64>,
+}
+
+fn plan(versions: &[u64], applied: &BTreeSet<u64>, target: Option<u64>) -> Result<Plan> {
+    if let Some(pair) = versions.windows(2).find(|pair| pair[0] >= pair[1]) {
+        return Err(MustyError::Other(anyhow::anyhow!(
+            "Migrations must be registered in ascending order of their versions, but {} is registered before {}",
+            pair[0],
+            pair[1]
+        )));
+    }
+
+    let target = target.unwrap_or_else(|| versions.last().copied().unwrap_or(0));
+
+    let down = applied
+        .iter()
+        .rev()
+        .copied()
+        .filter(|&version| version > target)
+        .map(|version| match versions.contains(&version) {
+            true => Ok(version),
+            false => Err(MustyError::Other(anyhow::anyhow!(
+                "Migration {} has been applied, but it is not registered",
+                version
+            ))),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let up = versions
+        .iter()
+        .enumerate()
+        .filter(|(_, version)| **version <= target && !applied.contains(version))
+        .map(|(index, _)| index)
+        .collect();
+
+    Ok(Plan { up, down })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{plan, Plan};
+
+    #[test]
+    fn plans() {
+        let applied = BTreeSet::from([1, 2]);
+
+        assert_eq!(
+            plan(&[1, 2, 3, 5], &applied, None).unwrap(),
+            Plan {
+                up: vec![2, 3],
+                down: vec![]
+            }
+        );
+        assert_eq!(
+            plan(&[1, 2, 3, 5], &applied, Some(3)).unwrap(),
+            Plan {
+                up: vec![2],
+                down: vec![]
+            }
+        );
+        assert_eq!(
+            plan(&[1, 2, 3], &applied, Some(0)).unwrap(),
+            Plan {
+                up: vec![],
+                down: vec![2, 1]
+            }
+        );
+
+        assert!(plan(&[2, 1], &BTreeSet::new(), None).is_err());
+        assert!(plan(&[1], &applied, Some(0)).is_err());
+    }
+}
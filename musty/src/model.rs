@@ -1,8 +1,9 @@
 use serde::{de::DeserializeOwned, Serialize};
+use std::{any::TypeId, time::SystemTime};
 
-use crate::{db::Db, Result};
+use crate::{db::Db, hooks::Hook, validation::ValidationErrors, Result};
 
-use crate::prelude::{Backend, Context, Id, IdGuard};
+use crate::prelude::{Backend, Context, Id, IdGuard, QueryOptions};
 
 use async_trait::async_trait;
 
@@ -28,40 +29,337 @@ where
     /// Set the ID of this model.
     fn set_id(&mut self, id: Id<Self, Self::Id>);
 
+    /// The name the version field of this model is stored with, if it has one (`#[musty(version)]` on an integer field).
+    /// A versioned model is only saved if the stored version is the version it was loaded with, see [`MustyError::VersionConflict`](crate::MustyError::VersionConflict)
+    const VERSION_FIELD: Option<&'static str> = None;
+
+    /// Get the version of this model, if it is versioned.
+    /// Fails with [`MustyError::VersionOutOfRange`](crate::MustyError::VersionOutOfRange) if the version does not fit in an `i64`
+    fn version(&self) -> Result<Option<i64>> {
+        Ok(None)
+    }
+
+    /// Set the version of this model, if it is versioned.
+    /// Fails with [`MustyError::VersionOutOfRange`](crate::MustyError::VersionOutOfRange) if the version does not fit in the version field
+    fn set_version(&mut self, _version: i64) -> Result<()> {
+        Ok(())
+    }
+
+    /// The name the soft delete marker of this model is stored with, if it has `#[model(soft_delete)]`.
+    /// Soft-deleted models are marked as deleted instead of being deleted, and are not found by default
+    const SOFT_DELETE_FIELD: Option<&'static str> = None;
+
+    /// Whether this model is soft-deleted.
+    fn is_deleted(&self) -> bool {
+        false
+    }
+
+    /// The time this model was soft-deleted at, if it is soft-deleted.
+    fn deleted_at(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Set or clear the soft delete marker of this model.
+    fn set_deleted_at(&mut self, _deleted_at: Option<SystemTime>) {}
+
+    /// The discriminator of this model, if it is a variant of an enum model (`#[model]` on an enum):
+    /// the name of the field storing the variant, and the value identifying this variant.
+    /// Variants are stored in the collection of their enum model, and their queries only find models of the same variant
+    const DISCRIMINATOR: Option<(&'static str, &'static str)> = None;
+
+    /// The type of the model owning the collection this model is stored in: the enum model of a variant, or this model
+    #[doc(hidden)]
+    fn collection_type() -> TypeId
+    where
+        Self: 'static,
+    {
+        TypeId::of::<Self>()
+    }
+
+    /// Whether this model has lifecycle hooks (`#[model(hooks)]`), see [`ModelHooks`](crate::prelude::ModelHooks)
+    const HOOKS: bool = false;
+
+    /// Run a lifecycle hook of this model, if it has `#[model(hooks)]`.
+    async fn run_hook<B: Backend>(&mut self, _db: &Db<B>, _hook: Hook) -> Result<()> {
+        Ok(())
+    }
+
+    /// Validate the fields of this model before it is saved, see [`validation`](crate::validation).
+    fn validate(&self) -> std::result::Result<(), ValidationErrors> {
+        Ok(())
+    }
+
+    /// Generate the id of this model before it is saved if it has none,
+    /// with the strategy of its `#[musty(id(generate = "..."))]` id field, see [`generate`](crate::generate).
+    /// Models without a strategy keep their id, which may then be generated by the database (ex: an `ObjectId` with MongoDB)
+    async fn generate_id<B>(&mut self, _db: &Db<B>) -> Result<()>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        B: Backend,
+    {
+        Ok(())
+    }
+
+    /// Whether this model has a `#[musty(created_at)]` field, in which case saving it checks if it is stored yet
+    const HAS_CREATED_AT: bool = false;
+
+    /// Fill in the timestamps of this model before it is saved:
+    /// the `#[musty(created_at)]` field is only set when `inserting` the model,
+    /// and the `#[musty(updated_at)]` field is always set.
+    fn set_timestamps(&mut self, _now: SystemTime, _inserting: bool) {}
+
     /// Get a model by its ID from a database.
+    /// Soft-deleted models are not found, see [`Model::get_by_id_with_deleted`]
     async fn get_by_id<B, T>(db: &Db<B>, id: T) -> Result<Option<Self>>
     where
         Self: Context<Self::Id, B> + 'static,
         T: Into<Id<Self, Self::Id>> + Send + Sync,
         B: Backend,
     {
-        db.inner.get_model_by_id(&id.into()).await
+        let model = db.inner.get_model_by_id(&id.into()).await?;
+        loaded(db, model.filter(|model: &Self| !model.is_deleted())).await
+    }
+
+    /// Get a model by its ID from a database, even if it is soft-deleted.
+    async fn get_by_id_with_deleted<B, T>(db: &Db<B>, id: T) -> Result<Option<Self>>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        T: Into<Id<Self, Self::Id>> + Send + Sync,
+        B: Backend,
+    {
+        let model = db.inner.get_model_by_id(&id.into()).await?;
+        loaded(db, model).await
+    }
+
+    /// Get every model with one of the given ids from a database in a single query, in no particular order.
+    /// Soft-deleted models and ids which are not found are skipped
+    async fn get_by_ids<B>(db: &Db<B>, ids: &[Id<Self, Self::Id>]) -> Result<Vec<Self>>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        B: Backend,
+    {
+        let mut models = db.inner.get_models_by_ids(ids).await?;
+        models.retain(|model| !model.is_deleted());
+        for model in models.iter_mut() {
+            model.run_hook(db, Hook::AfterLoad).await?;
+        }
+        Ok(models)
     }
 
     /// Save this model to a database.
-    async fn save<B>(&mut self, db: &Db<B>) -> Result<bool>
+    /// Returns whether the model was inserted, updated, or left unchanged, see [`SaveOutcome`]
+    ///
+    /// A model without an id first gets one from the id strategy of the model, if it has one (see [`Model::generate_id`]).
+    /// The `before_save` hook runs next (see [`ModelHooks`](crate::prelude::ModelHooks)), then the model is validated,
+    /// failing with [`MustyError::Validation`](crate::MustyError::Validation) listing every invalid field (see [`validation`](crate::validation)).
+    /// The timestamps of the model are then filled in, using the clock of the database (see [`Musty::with_clock`](crate::Musty::with_clock)).
+    /// If the model is versioned, the version is incremented by every save,
+    /// and saving fails with [`MustyError::VersionConflict`](crate::MustyError::VersionConflict) if the model was saved elsewhere since it was loaded
+    async fn save<B>(&mut self, db: &Db<B>) -> Result<SaveOutcome<Self>>
     where
         Self: Context<Self::Id, B> + 'static,
         B: Backend,
     {
-        db.inner.save_model(self).await
+        let new = self.id().is_none();
+        self.generate_id(db).await?;
+        self.run_hook(db, Hook::BeforeSave).await?;
+        self.validate()?;
+        // a model is inserted if it has no id yet, or if nothing is stored with its id
+        let inserting =
+            new || (Self::HAS_CREATED_AT && db.inner.get_model_by_id(self.id()).await?.is_none());
+        self.set_timestamps(db.now(), inserting);
+        let outcome = db.inner.save_model(self).await?;
+        self.run_hook(db, Hook::AfterSave).await?;
+        Ok(outcome)
     }
 
     /// Delete this model from a database.
+    ///
+    /// Models with `#[model(soft_delete)]` are marked as deleted instead, see [`Model::force_delete`] and [`Model::restore`].
+    /// Only the soft delete marker of the stored model is updated: the model is not saved, and is not inserted if it is not stored.
+    /// Returns `false` if the model was already deleted, or is not stored
     async fn delete<B>(&mut self, db: &Db<B>) -> Result<bool>
     where
         Self: Context<Self::Id, B> + 'static,
         B: Backend,
     {
-        db.inner.delete_model(self).await
+        if Self::SOFT_DELETE_FIELD.is_none() {
+            return self.force_delete(db).await;
+        }
+        if self.is_deleted() {
+            return Ok(false);
+        }
+
+        self.run_hook(db, Hook::BeforeDelete).await?;
+        self.set_deleted_at(Some(db.now()));
+        match db.inner.set_model_deleted_at(self).await {
+            Ok(true) => {}
+            Ok(false) => {
+                self.set_deleted_at(None);
+                return Ok(false);
+            }
+            Err(err) => {
+                self.set_deleted_at(None);
+                return Err(err);
+            }
+        }
+        self.run_hook(db, Hook::AfterDelete).await?;
+        Ok(true)
+    }
+
+    /// Restore this soft-deleted model, clearing its soft delete marker.
+    /// Like [`Model::delete`], only the soft delete marker of the stored model is updated, and no hooks are run (see [`ModelHooks`](crate::prelude::ModelHooks)).
+    /// Returns `false` if the model was not soft-deleted, or is not stored
+    async fn restore<B>(&mut self, db: &Db<B>) -> Result<bool>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        B: Backend,
+    {
+        if !self.is_deleted() {
+            return Ok(false);
+        }
+
+        let deleted_at = self.deleted_at();
+        self.set_deleted_at(None);
+        match db.inner.set_model_deleted_at(self).await {
+            Ok(true) => Ok(true),
+            Ok(false) => {
+                self.set_deleted_at(deleted_at);
+                Ok(false)
+            }
+            Err(err) => {
+                self.set_deleted_at(deleted_at);
+                Err(err)
+            }
+        }
+    }
+
+    /// Delete this model from a database, even if it has `#[model(soft_delete)]`.
+    async fn force_delete<B>(&mut self, db: &Db<B>) -> Result<bool>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        B: Backend,
+    {
+        self.run_hook(db, Hook::BeforeDelete).await?;
+        let deleted = db.inner.delete_model(self).await?;
+        if deleted {
+            self.run_hook(db, Hook::AfterDelete).await?;
+        }
+        Ok(deleted)
     }
 
     /// Find a single model from a database by a filter.
-    async fn find_one<B>(db: &Db<B>, filter: B::Filter) -> Result<Option<Self>>
+    /// The `options` can be used to sort the matching models or to skip some of them, see [`QueryOptions`](crate::prelude::QueryOptions)
+    async fn find_one<B, F, O>(db: &Db<B>, filter: F, options: O) -> Result<Option<Self>>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        B: Backend,
+        F: Into<B::Filter> + Send + Sync,
+        O: Into<Option<QueryOptions>> + Send,
+    {
+        let model = db.inner.find_one(filter, options.into()).await?;
+        loaded(db, model).await
+    }
+
+    /// Find all models matching a filter from a database.
+    /// Returns a cursor which can be used to iterate over the results, see [`MustyCursor`](crate::prelude::MustyCursor)
+    /// The `options` can be used to sort, limit, skip and project the results, see [`QueryOptions`](crate::prelude::QueryOptions)
+    async fn find_many<B, F, O>(db: &Db<B>, filter: F, options: O) -> Result<B::Cursor<Self>>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        B: Backend,
+        F: Into<B::Filter> + Send + Sync,
+        O: Into<Option<QueryOptions>> + Send,
+    {
+        let cursor = db.inner.find_many(filter, options.into()).await?;
+        if Self::HOOKS {
+            return Ok(B::with_hooks(cursor, db.clone()));
+        }
+        Ok(cursor)
+    }
+
+    /// Count the models matching a filter in a database.
+    /// Soft-deleted models are not counted
+    async fn count<B, F>(db: &Db<B>, filter: F) -> Result<u64>
     where
         Self: Context<Self::Id, B> + 'static,
         B: Backend,
+        F: Into<B::Filter> + Send + Sync,
     {
-        db.inner.find_one(filter).await
+        db.inner.count_models::<Self, _, _>(filter, None).await
+    }
+
+    /// Whether any model matches a filter in a database, without loading it.
+    /// Soft-deleted models are not found
+    async fn exists<B, F>(db: &Db<B>, filter: F) -> Result<bool>
+    where
+        Self: Context<Self::Id, B> + 'static,
+        B: Backend,
+        F: Into<B::Filter> + Send + Sync,
+    {
+        let options = QueryOptions::new().limit(1);
+        let count = db
+            .inner
+            .count_models::<Self, _, _>(filter, Some(options))
+            .await?;
+        Ok(count > 0)
+    }
+}
+
+/// Runs the `after_load` hook of a model loaded from a database, if any
+pub(crate) async fn loaded<M: Model, B: Backend>(
+    db: &Db<B>,
+    mut model: Option<M>,
+) -> Result<Option<M>> {
+    if let Some(model) = model.as_mut() {
+        model.run_hook(db, Hook::AfterLoad).await?;
+    }
+    Ok(model)
+}
+
+/// The outcome of saving a model, as reported by the database
+pub enum SaveOutcome<M: Model> {
+    /// The model did not exist yet and was inserted with the given id
+    Inserted { id: Id<M, M::Id> },
+    /// An existing model was found and updated
+    Updated,
+    /// An existing model was found, but it was already identical to the saved model
+    Unchanged,
+}
+
+impl<M: Model> SaveOutcome<M> {
+    pub fn is_inserted(&self) -> bool {
+        matches!(self, Self::Inserted { .. })
+    }
+
+    pub fn is_updated(&self) -> bool {
+        matches!(self, Self::Updated)
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Self::Unchanged)
+    }
+}
+
+impl<M: Model> std::fmt::Debug for SaveOutcome<M>
+where
+    M::Id: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Inserted { id } => f.debug_struct("Inserted").field("id", &id.inner).finish(),
+            Self::Updated => f.write_str("Updated"),
+            Self::Unchanged => f.write_str("Unchanged"),
+        }
+    }
+}
+
+impl<M: Model> PartialEq for SaveOutcome<M> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Inserted { id }, Self::Inserted { id: other }) => id == other,
+            (Self::Updated, Self::Updated) | (Self::Unchanged, Self::Unchanged) => true,
+            _ => false,
+        }
     }
 }
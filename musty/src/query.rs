@@ -0,0 +1,138 @@
+use serde_json::Value;
+
+use crate::{filter::Filter, Model};
+
+/// Database-agnostic options for finding models: sorting, limiting, skipping and projecting.
+///
+/// Each [`Backend`](crate::prelude::Backend) maps these to its native options (ex: `mongodb::options::FindOptions`).
+/// ```ignore
+/// let options = QueryOptions::new()
+///     .sort(User::fields().age.desc())
+///     .sort(User::fields().name.asc())
+///     .skip(20)
+///     .limit(10);
+/// let users = User::find_many(&db, filter!(User, age > 18), options).await?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryOptions {
+    /// The sort keys, in order of precedence
+    pub sort: Vec<Sort>,
+    /// The maximum number of models to return
+    pub limit: Option<u64>,
+    /// The number of matching models to skip
+    pub skip: Option<u64>,
+    /// The fields to load, all fields are loaded if `None`.
+    /// The model must be able to deserialize without the other fields (ex: they are `Option`s or have a `#[serde(default)]`)
+    pub projection: Option<Vec<String>>,
+    /// Whether soft-deleted models are found, for models with `#[model(soft_delete)]`. Excluded by default
+    pub deleted: DeletedScope,
+}
+
+impl QueryOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sorts by the given key, after any previously added sort keys
+    pub fn sort(mut self, sort: impl Into<Sort>) -> Self {
+        self.sort.push(sort.into());
+        self
+    }
+
+    /// Returns at most `limit` models
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skips the first `skip` matching models
+    pub fn skip(mut self, skip: u64) -> Self {
+        self.skip = Some(skip);
+        self
+    }
+
+    /// Only loads the given field (and the id), can be called multiple times to load multiple fields
+    pub fn project(mut self, field: impl Into<String>) -> Self {
+        self.projection
+            .get_or_insert_with(Vec::new)
+            .push(field.into());
+        self
+    }
+
+    /// Also finds soft-deleted models
+    pub fn with_deleted(mut self) -> Self {
+        self.deleted = DeletedScope::Include;
+        self
+    }
+
+    /// Only finds soft-deleted models
+    pub fn only_deleted(mut self) -> Self {
+        self.deleted = DeletedScope::Only;
+        self
+    }
+}
+
+/// Which soft-deleted models are found by a query, see [`QueryOptions::with_deleted`] and [`QueryOptions::only_deleted`].
+/// Has no effect for models without `#[model(soft_delete)]`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DeletedScope {
+    /// Soft-deleted models are not found
+    #[default]
+    Exclude,
+    /// Soft-deleted models are found along with the others
+    Include,
+    /// Only soft-deleted models are found
+    Only,
+}
+
+impl DeletedScope {
+    /// The filter on the soft delete marker of a model for this scope, if any
+    pub(crate) fn filter<M: Model>(&self) -> Option<Filter> {
+        let field = M::SOFT_DELETE_FIELD?;
+        match self {
+            Self::Exclude => Some(Filter::Eq(field.to_string(), Value::Null)),
+            Self::Include => None,
+            Self::Only => Some(Filter::Ne(field.to_string(), Value::Null)),
+        }
+    }
+}
+
+/// The filter restricting the queries of a model, if any:
+/// to its variant if it is a variant of an enum model (see [`Model::DISCRIMINATOR`]), and to the soft delete scope
+pub(crate) fn scope_filter<M: Model>(deleted: DeletedScope) -> Option<Filter> {
+    let variant = M::DISCRIMINATOR.map(|(field, value)| Filter::eq(field, value));
+    match (variant, deleted.filter::<M>()) {
+        (Some(variant), Some(deleted)) => Some(variant.and(deleted)),
+        (variant, deleted) => variant.or(deleted),
+    }
+}
+
+/// A sort key: a field, and the order to sort it in.
+/// Usually created from a typed field path (ex: `User::fields().name.asc()`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Sort {
+    pub field: String,
+    pub order: SortOrder,
+}
+
+impl Sort {
+    pub fn asc(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            order: SortOrder::Ascending,
+        }
+    }
+
+    pub fn desc(field: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            order: SortOrder::Descending,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
@@ -0,0 +1,198 @@
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    db::Db,
+    field::FieldValue,
+    filter::Filter,
+    id::{Id, IdGuard},
+    prelude::{Backend, Context},
+    Model, Result,
+};
+
+/// A reference to another model, stored as the id of the referenced model.
+///
+/// The referenced model is not loaded with the model holding the reference, it is loaded on demand with [`Ref::fetch`],
+/// or for many references at once with [`populate`]:
+/// ```ignore
+/// #[model(mongo(collection = "posts"))]
+/// struct Post {
+///     id: ObjectId,
+///     author: Ref<User>,
+/// }
+///
+/// let mut post = Post::get_by_id(&db, id).await?.unwrap();
+/// let author = post.author.fetch(&db).await?;
+/// ```
+pub struct Ref<M: Model> {
+    id: Id<M, M::Id>,
+    model: Option<Arc<M>>,
+}
+
+impl<M: Model> Ref<M> {
+    /// A reference to the model with the given id, which is not loaded yet
+    pub fn new(id: impl Into<Id<M, M::Id>>) -> Self {
+        Self {
+            id: id.into(),
+            model: None,
+        }
+    }
+
+    /// The id of the referenced model
+    pub fn id(&self) -> &Id<M, M::Id> {
+        &self.id
+    }
+
+    /// The referenced model, if it has been loaded
+    pub fn get(&self) -> Option<&M> {
+        self.model.as_deref()
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.model.is_some()
+    }
+
+    /// Get the referenced model, loading it from a database with [`Model::get_by_id`] if it is not loaded yet.
+    /// Returns `None` if the referenced model does not exist
+    pub async fn fetch<B>(&mut self, db: &Db<B>) -> Result<Option<&M>>
+    where
+        M: Context<M::Id, B> + 'static,
+        B: Backend,
+    {
+        if self.model.is_none() && !self.id.is_none() {
+            self.model = M::get_by_id(db, self.id.clone()).await?.map(Arc::new);
+        }
+        Ok(self.get())
+    }
+
+    /// Sets the referenced model, which is then loaded
+    pub fn set(&mut self, model: M) {
+        self.id = model.id().clone();
+        self.model = Some(Arc::new(model));
+    }
+
+    /// Unloads the referenced model, so the next [`Ref::fetch`] loads it again
+    pub fn unload(&mut self) {
+        self.model = None;
+    }
+}
+
+/// Loads the referenced models of many models in a single query (a [`Model::find_many`] with `$in` on their ids), instead of a query per model.
+///
+/// `reference` selects the reference to load from each model. References which are already loaded are not loaded again:
+/// ```ignore
+/// let mut posts: Vec<Post> = Post::find_many(&db, filter!(Post, published == true), None)
+///     .await?
+///     .try_collect()
+///     .await?;
+/// populate(&db, &mut posts, |post| &mut post.author).await?;
+/// ```
+pub async fn populate<P, M, B, F>(db: &Db<B>, models: &mut [P], mut reference: F) -> Result<()>
+where
+    M: Model + Context<M::Id, B> + 'static,
+    B: Backend,
+    F: FnMut(&mut P) -> &mut Ref<M>,
+    Filter: Into<B::Filter>,
+{
+    let ids = models
+        .iter_mut()
+        .map(&mut reference)
+        .filter(|reference| !reference.is_loaded() && !reference.id.is_none())
+        .map(|reference| reference.id.clone())
+        .collect::<Vec<_>>();
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let found: Vec<M> = M::find_many(db, Filter::in_("_id", ids), None)
+        .await?
+        .try_collect()
+        .await?;
+    let loaded = found
+        .into_iter()
+        .filter_map(|model| Some((key(model.id())?, Arc::new(model))))
+        .collect::<HashMap<_, _>>();
+
+    for model in models.iter_mut() {
+        let reference = reference(model);
+        if reference.is_loaded() {
+            continue;
+        }
+        if let Some(model) = key(&reference.id).and_then(|key| loaded.get(&key)) {
+            reference.model = Some(model.clone());
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn key<M: Model>(id: &Id<M, M::Id>) -> Option<String> {
+    id.inner.as_ref().map(ToString::to_string)
+}
+
+impl<M: Model> Clone for Ref<M> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id.clone(),
+            model: self.model.clone(),
+        }
+    }
+}
+
+/// References are equal if they reference the same model
+impl<M: Model> PartialEq for Ref<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<M: Model> std::fmt::Debug for Ref<M>
+where
+    M: std::fmt::Debug,
+    M::Id: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ref")
+            .field("id", &self.id.inner)
+            .field("model", &self.model)
+            .finish()
+    }
+}
+
+impl<M: Model> From<Id<M, M::Id>> for Ref<M> {
+    fn from(id: Id<M, M::Id>) -> Self {
+        Self::new(id)
+    }
+}
+
+/// A reference to a model which is already loaded
+impl<M: Model> From<M> for Ref<M> {
+    fn from(model: M) -> Self {
+        Self {
+            id: model.id().clone(),
+            model: Some(Arc::new(model)),
+        }
+    }
+}
+
+impl<M: Model> Serialize for Ref<M> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.id.serialize(serializer)
+    }
+}
+
+impl<'de, M: Model> Deserialize<'de> for Ref<M> {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Ok(Self::new(Id::<M, M::Id>::deserialize(deserializer)?))
+    }
+}
+
+impl<M: Model<Id = I>, I: IdGuard> FieldValue<Ref<M>> for I {}
+
+impl<M: Model> FieldValue<Ref<M>> for Id<M, M::Id> {}
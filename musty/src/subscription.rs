@@ -0,0 +1,108 @@
+//! GraphQL subscriptions to the changes of models, fed by MongoDB change streams.
+//!
+//! [`subscribe`] watches the collection of a model (see [`MongoModel::collection`]) for inserts, updates, replacements and deletes,
+//! and streams them as [`ModelChange`]s, which a subscription field can return as they are:
+//! ```ignore
+//! #[Subscription]
+//! impl Subscription {
+//!     async fn posts(
+//!         &self,
+//!         ctx: &Context<'_>,
+//!         author: String,
+//!     ) -> async_graphql::Result<impl Stream<Item = musty::Result<ModelChange<Post>>>> {
+//!         let db = ctx.data_unchecked::<Musty<mongodb::Database>>();
+//!         Ok(subscribe(db, filter!(Post, author == author)).await?)
+//!     }
+//! }
+//! ```
+//! Change streams need a replica set or a sharded cluster, they are not available on standalone MongoDB servers.
+
+use std::borrow::Cow;
+
+use async_graphql::{Enum, OutputType, SimpleObject, TypeName};
+use bson::{doc, Document};
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use mongodb::{
+    options::{ChangeStreamOptions, FullDocumentType},
+    Database,
+};
+
+use crate::{
+    db::Db, filter::Filter, id::Id, model::loaded, ChangeEvent, Model, MongoModel, Result,
+};
+
+/// The kind of change made to a model
+#[derive(Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeOperation {
+    Insert,
+    Update,
+    Replace,
+    Delete,
+}
+
+/// A change made to a model, named after the GraphQL name of the model (ex: `PostChange`)
+#[derive(SimpleObject, Debug)]
+#[graphql(name_type)]
+pub struct ModelChange<M: Model + OutputType> {
+    pub operation: ChangeOperation,
+    /// The id of the changed model
+    pub id: Id<M, M::Id>,
+    /// The model after the change: `None` for deletes, and for updates of models which have been deleted since
+    pub model: Option<M>,
+}
+
+impl<M: Model + OutputType> TypeName for ModelChange<M> {
+    fn type_name() -> Cow<'static, str> {
+        format!("{}Change", M::type_name()).into()
+    }
+}
+
+/// Watch the changes of the models of type `M`, as a stream of [`ModelChange`]s, see [`MongoModel::watch`].
+///
+/// The filter (ex: `filter!(Post, author == "jonah")`) is matched against the model after each change,
+/// so models which stop matching it are not sent, and deletes (which have no model to match against) are always sent.
+/// The variants of an enum model only get the changes of their variant, except for deletes
+pub async fn subscribe<M>(
+    db: &Db<Database>,
+    filter: impl Into<Option<Filter>>,
+) -> Result<BoxStream<'static, Result<ModelChange<M>>>>
+where
+    M: MongoModel + OutputType + 'static,
+{
+    let pipeline = filter
+        .into()
+        .map(|filter| {
+            let filter = Filter::eq("operationType", "delete").or(filter.nested("fullDocument"));
+            Document::try_from(filter).map(|filter| doc! { "$match": filter })
+        })
+        .transpose()?;
+    let options = ChangeStreamOptions::builder()
+        .full_document(Some(FullDocumentType::UpdateLookup))
+        .build();
+    let stream = M::watch(db, pipeline, options).await?;
+
+    let db = db.clone();
+    Ok(stream
+        .and_then(move |change| {
+            let db = db.clone();
+            async move {
+                let (operation, id, model) = match change {
+                    ChangeEvent::Insert(model) => {
+                        (ChangeOperation::Insert, model.id().clone(), Some(model))
+                    }
+                    ChangeEvent::Update { id, model, .. } => (ChangeOperation::Update, id, model),
+                    ChangeEvent::Replace(model) => {
+                        (ChangeOperation::Replace, model.id().clone(), Some(model))
+                    }
+                    ChangeEvent::Delete(id) => (ChangeOperation::Delete, id, None),
+                };
+                let model = loaded(&db, model).await?;
+                Ok(ModelChange {
+                    operation,
+                    id,
+                    model,
+                })
+            }
+        })
+        .boxed())
+}
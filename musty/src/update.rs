@@ -0,0 +1,102 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::filter::to_value;
+
+/// A database-agnostic update of the fields of models.
+///
+/// Updates are usually built from the typed fields of a model, and combined with [`Update::and`]:
+/// ```ignore
+/// let update = User::fields().name.set("jonah").and(User::fields().logins.inc(1));
+/// User::find_one_and_update(&db, filter!(User, id == user_id), Document::try_from(update)?, None).await?;
+/// ```
+/// Like a [`Filter`](crate::filter::Filter), values are held in their serialized form, and fields are referred to by the name they are stored with in the database.
+/// A value which can not be serialized makes the update [`Update::Invalid`], and translating it fails with [`MustyError::InvalidUpdate`](crate::MustyError::InvalidUpdate).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Update {
+    /// Sets the field to the value
+    Set(String, Value),
+    /// Increments the field by the value
+    Inc(String, Value),
+    /// Removes the field
+    Unset(String),
+    /// All of the updates are applied
+    And(Vec<Update>),
+    /// A value of the update could not be serialized, with the serialization error
+    Invalid(String),
+}
+
+impl Update {
+    /// Sets the field to the value
+    pub fn set<V: Serialize>(field: impl Into<String>, value: V) -> Self {
+        match to_value(value) {
+            Ok(value) => Self::Set(field.into(), value),
+            Err(err) => Self::Invalid(err.to_string()),
+        }
+    }
+
+    /// Increments the field by the value (decrements it, if the value is negative)
+    pub fn inc<V: Serialize>(field: impl Into<String>, value: V) -> Self {
+        match to_value(value) {
+            Ok(value) => Self::Inc(field.into(), value),
+            Err(err) => Self::Invalid(err.to_string()),
+        }
+    }
+
+    /// Removes the field
+    pub fn unset(field: impl Into<String>) -> Self {
+        Self::Unset(field.into())
+    }
+
+    /// Both this update and `other` are applied
+    pub fn and(self, other: Update) -> Self {
+        match self {
+            Self::And(mut updates) => {
+                updates.push(other);
+                Self::And(updates)
+            }
+            update => Self::And(vec![update, other]),
+        }
+    }
+
+    /// The serialization error of the first invalid value of this update, if any
+    pub fn error(&self) -> Option<&str> {
+        match self {
+            Self::Invalid(err) => Some(err),
+            Self::And(updates) => updates.iter().find_map(Update::error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Update;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn combine() {
+        let update = Update::set("name", "jonah")
+            .and(Update::inc("logins", 1))
+            .and(Update::unset("email"));
+
+        assert_eq!(
+            update,
+            Update::And(vec![
+                Update::Set("name".to_string(), json!("jonah")),
+                Update::Inc("logins".to_string(), json!(1)),
+                Update::Unset("email".to_string()),
+            ])
+        );
+        assert_eq!(update.error(), None);
+    }
+
+    #[test]
+    fn invalid_values() {
+        // maps with non-string keys can not be serialized to JSON
+        let tags = HashMap::from([((1, 2), "a")]);
+        let update = Update::set("name", "jonah").and(Update::set("tags", &tags));
+        assert!(update.error().is_some());
+    }
+}
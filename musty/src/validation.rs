@@ -0,0 +1,286 @@
+//! Validation of model fields, run by [`Model::save`](crate::prelude::Model::save) before a model is written.
+//!
+//! Validations are declared on the fields of a model with the [`model`](crate::prelude::model) macro:
+//! ```ignore
+//! #[model(mongo())]
+//! struct User {
+//!     id: ObjectId,
+//!     #[musty(validate(length(min = 1, max = 64), regex = "^[a-z0-9_]+$"))]
+//!     username: String,
+//!     #[musty(validate(email))]
+//!     email: Option<String>,
+//!     #[musty(validate(range(min = 13), custom = "not_reserved"))]
+//!     age: u32,
+//! }
+//!
+//! fn not_reserved(age: &u32) -> Result<(), String> {
+//!     match age {
+//!         42 => Err("is reserved".to_string()),
+//!         _ => Ok(()),
+//!     }
+//! }
+//! ```
+//! Every failing field is reported in a [`ValidationErrors`], returned as [`MustyError::Validation`](crate::MustyError::Validation).
+//! `None` values are never validated, so optional fields are only validated when they are set.
+
+use std::fmt::Display;
+
+#[doc(hidden)]
+pub use regex::Regex;
+
+/// The failed validations of a model
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationErrors {
+    errors: Vec<FieldError>,
+}
+
+/// A failed validation of a field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// The name the field is stored with
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a failed validation of the given field
+    pub fn add(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// The failed validations of the given field
+    pub fn field(&self, field: &str) -> impl Iterator<Item = &FieldError> {
+        let field = field.to_string();
+        self.errors.iter().filter(move |error| error.field == field)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// `Ok` if no validation failed
+    pub fn into_result(self) -> Result<(), Self> {
+        match self.is_empty() {
+            true => Ok(()),
+            false => Err(self),
+        }
+    }
+}
+
+impl Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let errors = self
+            .errors
+            .iter()
+            .map(|error| format!("{} {}", error.field, error.message))
+            .collect::<Vec<_>>();
+        write!(f, "Validation failed: {}", errors.join(", "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// A value with a length: strings (in characters) and collections
+pub trait ValidateLength {
+    /// The length of the value, `None` if there is nothing to validate
+    fn validated_length(&self) -> Option<usize>;
+}
+
+impl ValidateLength for String {
+    fn validated_length(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
+}
+
+impl ValidateLength for str {
+    fn validated_length(&self) -> Option<usize> {
+        Some(self.chars().count())
+    }
+}
+
+impl<T> ValidateLength for Vec<T> {
+    fn validated_length(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl<T: ValidateLength> ValidateLength for Option<T> {
+    fn validated_length(&self) -> Option<usize> {
+        self.as_ref()?.validated_length()
+    }
+}
+
+/// A string value
+pub trait ValidateStr {
+    /// The string, `None` if there is nothing to validate
+    fn validated_str(&self) -> Option<&str>;
+}
+
+impl ValidateStr for String {
+    fn validated_str(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl ValidateStr for str {
+    fn validated_str(&self) -> Option<&str> {
+        Some(self)
+    }
+}
+
+impl<T: ValidateStr> ValidateStr for Option<T> {
+    fn validated_str(&self) -> Option<&str> {
+        self.as_ref()?.validated_str()
+    }
+}
+
+/// A numeric value
+pub trait ValidateNumber {
+    /// The number, `None` if there is nothing to validate
+    fn validated_number(&self) -> Option<f64>;
+}
+
+macro_rules! impl_validate_number {
+    ($($ty:ty),*) => {
+        $(
+            impl ValidateNumber for $ty {
+                fn validated_number(&self) -> Option<f64> {
+                    Some(*self as f64)
+                }
+            }
+        )*
+    };
+}
+
+impl_validate_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl<T: ValidateNumber> ValidateNumber for Option<T> {
+    fn validated_number(&self) -> Option<f64> {
+        self.as_ref()?.validated_number()
+    }
+}
+
+/// The length of the value is within the bounds
+pub fn length<T: ValidateLength + ?Sized>(
+    value: &T,
+    min: Option<usize>,
+    max: Option<usize>,
+) -> Result<(), String> {
+    let length = match value.validated_length() {
+        Some(length) => length,
+        None => return Ok(()),
+    };
+    match (min, max) {
+        (Some(min), Some(max)) if length < min || length > max => {
+            Err(format!("must have a length between {} and {}", min, max))
+        }
+        (Some(min), _) if length < min => Err(format!("must have a length of at least {}", min)),
+        (_, Some(max)) if length > max => Err(format!("must have a length of at most {}", max)),
+        _ => Ok(()),
+    }
+}
+
+/// The value is within the bounds
+pub fn range<T: ValidateNumber + ?Sized>(
+    value: &T,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> Result<(), String> {
+    let number = match value.validated_number() {
+        Some(number) => number,
+        None => return Ok(()),
+    };
+    match (min, max) {
+        (Some(min), Some(max)) if number < min || number > max => {
+            Err(format!("must be between {} and {}", min, max))
+        }
+        (Some(min), _) if number < min => Err(format!("must be at least {}", min)),
+        (_, Some(max)) if number > max => Err(format!("must be at most {}", max)),
+        _ => Ok(()),
+    }
+}
+
+/// The value looks like an email address: a local part and a domain with a dot, separated by a single `@`, without whitespace
+pub fn email<T: ValidateStr + ?Sized>(value: &T) -> Result<(), String> {
+    let value = match value.validated_str() {
+        Some(value) => value,
+        None => return Ok(()),
+    };
+    let valid = match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !value.chars().any(char::is_whitespace)
+        }
+        None => false,
+    };
+    match valid {
+        true => Ok(()),
+        false => Err("must be a valid email address".to_string()),
+    }
+}
+
+/// The value matches the regex
+pub fn regex<T: ValidateStr + ?Sized>(value: &T, regex: &Regex) -> Result<(), String> {
+    match value.validated_str() {
+        Some(value) if !regex.is_match(value) => {
+            Err(format!("must match the pattern `{}`", regex.as_str()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{email, length, range, regex, Regex, ValidationErrors};
+
+    #[test]
+    fn validators() {
+        assert!(length("jonah", Some(1), Some(64)).is_ok());
+        assert!(length("", Some(1), None).is_err());
+        assert!(length(&Some("a very long name".to_string()), None, Some(4)).is_err());
+        assert!(length(&None::<String>, Some(1), None).is_ok());
+        assert!(length(&vec![1, 2, 3], Some(1), Some(2)).is_err());
+
+        assert!(range(&18_u32, Some(0.0), None).is_ok());
+        assert!(range(&-1_i64, Some(0.0), None).is_err());
+        assert!(range(&Some(2.5_f64), None, Some(2.0)).is_err());
+
+        assert!(email("jonah@example.com").is_ok());
+        assert!(email("jonah@example").is_err());
+        assert!(email("jonah example@example.com").is_err());
+        assert!(email("@example.com").is_err());
+
+        let username = Regex::new("^[a-z]+$").unwrap();
+        assert!(regex("jonah", &username).is_ok());
+        assert!(regex("Jonah", &username).is_err());
+    }
+
+    #[test]
+    fn errors() {
+        let mut errors = ValidationErrors::new();
+        assert_eq!(errors.clone().into_result(), Ok(()));
+
+        errors.add("name", "must have a length of at least 1");
+        errors.add("email", "must be a valid email address");
+        assert_eq!(errors.field("name").count(), 1);
+        assert_eq!(
+            errors.to_string(),
+            "Validation failed: name must have a length of at least 1, email must be a valid email address"
+        );
+    }
+}
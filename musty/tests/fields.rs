@@ -0,0 +1,59 @@
+#![cfg(feature = "mongodb")]
+
+use bson::{doc, oid::ObjectId};
+use musty::prelude::*;
+
+#[model(mongo(collection = "users"))]
+struct User {
+    id: ObjectId,
+    name: String,
+    #[musty(rename = "years")]
+    age: u32,
+    email: Option<String>,
+}
+
+#[test]
+fn paths() {
+    assert_eq!(User::fields().id.path(), "_id");
+    assert_eq!(User::fields().name.path(), "name");
+    assert_eq!(User::fields().age.path(), "years");
+    assert_eq!(User::fields().age.to_string(), "years");
+}
+
+#[test]
+fn typed_filters() {
+    let id = ObjectId::new();
+    assert_eq!(User::fields().id.eq(id), Filter::eq("_id", id));
+    assert_eq!(User::fields().name.eq("jonah"), Filter::eq("name", "jonah"));
+    assert_eq!(
+        User::fields().age.in_([18, 21]),
+        Filter::in_("years", [18, 21])
+    );
+    assert_eq!(
+        filter!(User, age > 18 && exists(name)),
+        Filter::And(vec![Filter::gt("years", 18), Filter::exists("name", true)])
+    );
+}
+
+#[test]
+fn usable_as_document_keys() {
+    assert_eq!(
+        doc! { "$set": { User::fields().age: 30 } },
+        doc! { "$set": { "years": 30 } }
+    );
+}
+
+#[test]
+fn typed_updates() {
+    assert_eq!(
+        User::fields().name.set("jonah"),
+        Update::set("name", "jonah")
+    );
+    assert_eq!(User::fields().age.inc(1), Update::inc("years", 1));
+    assert_eq!(User::fields().email.unset(), Update::unset("email"));
+    assert_eq!(
+        bson::Document::try_from(User::fields().age.inc(1).and(User::fields().email.unset()))
+            .unwrap(),
+        doc! { "$inc": { "years": 1 }, "$unset": { "email": "" } }
+    );
+}
@@ -0,0 +1,286 @@
+#![cfg(all(feature = "graphql", feature = "memory"))]
+
+use async_graphql::{
+    connection::Connection, Context, EmptyMutation, EmptySubscription, Object, Request, Schema,
+};
+use futures::executor::block_on;
+use musty::prelude::*;
+use serde_json::json;
+
+#[model(graphql)]
+#[derive(Clone)]
+struct Author {
+    id: u32,
+    /// The name shown on posts
+    #[musty(graphql(name = "displayName"))]
+    name: String,
+    #[musty(graphql(skip))]
+    password_hash: String,
+    #[musty(version)]
+    version: u64,
+}
+
+#[model(graphql(name = "Article", input = "ArticleDraft"))]
+struct Post {
+    id: u32,
+    title: String,
+    published: bool,
+}
+
+struct Query;
+
+#[Object]
+impl Query {
+    async fn author(&self) -> Author {
+        Author {
+            id: 1.into(),
+            name: "jonah".to_string(),
+            password_hash: "hash".to_string(),
+            version: 3,
+        }
+    }
+
+    async fn post(&self, draft: ArticleDraft) -> Post {
+        draft.into()
+    }
+
+    async fn author_by_id(
+        &self,
+        ctx: &Context<'_>,
+        id: u32,
+    ) -> async_graphql::Result<Option<Author>> {
+        let loader = ctx.data_unchecked::<ModelLoader<Author, MemoryBackend>>();
+        Ok(loader.load(id).await?)
+    }
+}
+
+#[test]
+fn models_are_graphql_objects() {
+    let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+    let response = block_on(schema.execute(
+        r#"{
+            author { id displayName version }
+            post(draft: { title: "musty", published: true }) { id title published }
+        }"#,
+    ));
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    assert_eq!(
+        response.data.into_json().unwrap(),
+        json!({
+            "author": { "id": 1, "displayName": "jonah", "version": 3 },
+            "post": { "id": null, "title": "musty", "published": true },
+        })
+    );
+
+    let sdl = schema.sdl();
+    assert!(sdl.contains("type Article"));
+    assert!(sdl.contains("input ArticleDraft"));
+    assert!(!sdl.contains("passwordHash"));
+}
+
+#[test]
+fn inputs_create_and_update_models() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        // the password hash is hidden from GraphQL, so it is passed to `into_model`
+        let mut author = AuthorInput {
+            name: "jonah".to_string(),
+        }
+        .into_model("hash".to_string());
+        assert!(author.id.is_none());
+        assert_eq!(author.password_hash, "hash");
+        author.id = 1.into();
+        author.save(&db).await?;
+
+        AuthorInput {
+            name: "alex".to_string(),
+        }
+        .update(&mut author);
+        author.save(&db).await?;
+
+        let found = Author::get_by_id(&db, 1)
+            .await?
+            .expect("author should exist");
+        assert_eq!(found.name, "alex");
+        assert_eq!(found.version, 2);
+        Ok(())
+    })
+}
+
+#[test]
+fn resolvers_load_models_with_a_loader() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+        for (id, name) in [(1, "jonah"), (2, "alex")] {
+            Author {
+                id: id.into(),
+                name: name.to_string(),
+                password_hash: String::new(),
+                version: 0,
+            }
+            .save(&db)
+            .await?;
+        }
+
+        let schema = Schema::new(Query, EmptyMutation, EmptySubscription);
+        let request = Request::new(
+            "{ first: authorById(id: 1) { displayName } second: authorById(id: 2) { displayName } missing: authorById(id: 3) { id } }",
+        )
+        .data(ModelLoader::<Author, _>::new(&db));
+        let response = schema.execute(request).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data.into_json().unwrap(),
+            json!({
+                "first": { "displayName": "jonah" },
+                "second": { "displayName": "alex" },
+                "missing": null,
+            })
+        );
+        Ok(())
+    })
+}
+
+#[model(graphql)]
+struct Player {
+    id: u32,
+    points: u32,
+}
+
+struct Leaderboard;
+
+#[Object]
+impl Leaderboard {
+    async fn players(
+        &self,
+        ctx: &Context<'_>,
+        after: Option<String>,
+        before: Option<String>,
+        first: Option<i32>,
+        last: Option<i32>,
+    ) -> async_graphql::Result<Connection<String, Player>> {
+        let db = ctx.data_unchecked::<Musty<MemoryBackend>>();
+        Ok(ConnectionQuery::new(filter!(Player, points > 0))
+            .sort(Player::fields().points.desc())
+            .after(after)
+            .before(before)
+            .first(first)
+            .last(last)
+            .load(db)
+            .await?)
+    }
+}
+
+#[test]
+fn connections_page_through_models() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+        for (id, points) in [(1, 30), (2, 10), (3, 20), (4, 10), (5, 30)] {
+            Player {
+                id: id.into(),
+                points,
+            }
+            .save(&db)
+            .await?;
+        }
+
+        let schema = Schema::build(Leaderboard, EmptyMutation, EmptySubscription)
+            .data(db.clone())
+            .finish();
+        // the ids and cursors of a page, and whether there are next and previous pages
+        let page = |arguments: String| {
+            let request = format!(
+                "{{ players({}) {{ edges {{ cursor node {{ id }} }} pageInfo {{ hasNextPage hasPreviousPage }} }} }}",
+                arguments
+            );
+            let schema = &schema;
+            async move {
+                let response = schema.execute(request).await;
+                assert!(response.errors.is_empty(), "{:?}", response.errors);
+                let data = response.data.into_json().unwrap();
+                let players = &data["players"];
+                let edges = players["edges"].as_array().cloned().unwrap_or_default();
+                (
+                    edges
+                        .iter()
+                        .map(|edge| edge["node"]["id"].clone())
+                        .collect::<Vec<_>>(),
+                    edges
+                        .iter()
+                        .map(|edge| edge["cursor"].clone())
+                        .collect::<Vec<_>>(),
+                    players["pageInfo"]["hasNextPage"] == json!(true),
+                    players["pageInfo"]["hasPreviousPage"] == json!(true),
+                )
+            }
+        };
+
+        let (ids, cursors, next, previous) = page("first: 2".to_string()).await;
+        assert_eq!(ids, vec![json!(1), json!(5)]);
+        assert!(next && !previous);
+
+        // cursors are JSON strings, which are quoted like GraphQL strings
+        let (ids, cursors, next, previous) = page(format!("after: {}, first: 2", cursors[1])).await;
+        assert_eq!(ids, vec![json!(3), json!(2)]);
+        assert!(next && previous);
+
+        let cursor = cursors[1].clone();
+        let (ids, _, next, _) = page(format!("after: {}, first: 2", cursor)).await;
+        assert_eq!(ids, vec![json!(4)]);
+        assert!(!next);
+
+        let (ids, _, next, previous) = page(format!("before: {}, last: 2", cursor)).await;
+        assert_eq!(ids, vec![json!(5), json!(3)]);
+        assert!(next && previous);
+
+        let (ids, _, next, previous) = page("last: 2".to_string()).await;
+        assert_eq!(ids, vec![json!(2), json!(4)]);
+        assert!(!next && previous);
+
+        let query = || ConnectionQuery::new(filter!(Player, points > 0));
+        assert!(matches!(
+            query()
+                .after(Some("cursor".to_string()))
+                .load::<Player, _>(&db)
+                .await,
+            Err(MustyError::InvalidPagination(_))
+        ));
+        assert!(matches!(
+            query().first(Some(-1)).load::<Player, _>(&db).await,
+            Err(MustyError::InvalidPagination(_))
+        ));
+        Ok(())
+    })
+}
+
+#[cfg(feature = "mongodb")]
+#[test]
+fn model_changes_are_named_after_models() {
+    use async_graphql::{Subscription, TypeName};
+    use futures::Stream;
+    use musty::subscription::{ChangeOperation, ModelChange};
+
+    struct Changes;
+
+    #[Subscription]
+    impl Changes {
+        async fn authors(&self) -> impl Stream<Item = ModelChange<Author>> {
+            futures::stream::empty()
+        }
+    }
+
+    assert_eq!(<ModelChange<Author>>::type_name(), "AuthorChange");
+    assert_eq!(<ModelChange<Post>>::type_name(), "ArticleChange");
+    let sdl = Schema::new(Query, EmptyMutation, Changes).sdl();
+    assert!(sdl.contains("type AuthorChange"));
+    assert!(sdl.contains("operation: ChangeOperation!"));
+    assert!(sdl.contains("model: Author"));
+
+    let change = ModelChange::<Author> {
+        operation: ChangeOperation::Delete,
+        id: 1.into(),
+        model: None,
+    };
+    assert_eq!(change.operation, ChangeOperation::Delete);
+}
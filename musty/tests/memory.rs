@@ -0,0 +1,1004 @@
+#![cfg(feature = "memory")]
+
+use futures::{executor::block_on, TryStreamExt};
+use musty::{backend::memory::MemoryFilter, prelude::*};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::time::{Duration, SystemTime};
+
+#[model]
+struct User {
+    id: u32,
+    name: String,
+    #[musty(rename = "years")]
+    age: u32,
+    email: Option<String>,
+}
+
+fn user(id: u32, name: &str) -> User {
+    User {
+        id: id.into(),
+        name: name.to_string(),
+        age: 20,
+        email: None,
+    }
+}
+
+#[test]
+fn save_and_get_by_id() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        assert_eq!(
+            user(1, "jonah").save(&db).await?,
+            SaveOutcome::Inserted { id: 1.into() }
+        );
+        assert_eq!(user(1, "alex").save(&db).await?, SaveOutcome::Updated);
+        assert_eq!(user(1, "alex").save(&db).await?, SaveOutcome::Unchanged);
+
+        let found = User::get_by_id(&db, 1).await?.expect("user should exist");
+        assert_eq!(found.id, 1);
+        assert_eq!(found.name, "alex");
+
+        assert!(User::get_by_id(&db, 2).await?.is_none());
+        Ok(())
+    })
+}
+
+#[test]
+fn delete() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        let mut jonah = user(1, "jonah");
+        jonah.save(&db).await?;
+
+        assert!(jonah.delete(&db).await?);
+        assert!(!jonah.delete(&db).await?);
+        assert!(User::get_by_id(&db, 1).await?.is_none());
+        Ok(())
+    })
+}
+
+#[test]
+fn find_one() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        user(1, "jonah").save(&db).await?;
+        user(2, "alex").save(&db).await?;
+
+        let alex = User::find_one(&db, json!({ "name": "alex" }), None).await?;
+        assert_eq!(alex.map(|user| user.id), Some(2.into()));
+
+        let nobody = User::find_one(&db, json!({ "name": "nobody" }), None).await?;
+        assert!(nobody.is_none());
+        Ok(())
+    })
+}
+
+#[test]
+fn find_one_with_filter_macro() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        user(1, "jonah").save(&db).await?;
+        let mut alex = user(2, "alex");
+        alex.age = 30;
+        alex.email = Some(String::from("alex@example.com"));
+        alex.save(&db).await?;
+
+        let found = User::find_one(&db, filter!(User, age > 25), None).await?;
+        assert_eq!(found.map(|user| user.id), Some(2.into()));
+
+        let found = User::find_one(&db, filter!(User, name != "alex" && age < 25), None).await?;
+        assert_eq!(found.map(|user| user.id), Some(1.into()));
+
+        let found = User::find_one(&db, filter!(User, name in ["bob", "alex"]), None).await?;
+        assert_eq!(found.map(|user| user.id), Some(2.into()));
+
+        let found = User::find_one(&db, filter!(User, !(name == "alex" || age > 25)), None).await?;
+        assert_eq!(found.map(|user| user.id), Some(1.into()));
+
+        let found =
+            User::find_one(&db, filter!(User, exists(email) && email != None), None).await?;
+        assert_eq!(found.map(|user| user.id), Some(2.into()));
+
+        let found = User::find_one(&db, filter!(User, name == "nobody" || age > 100), None).await?;
+        assert!(found.is_none());
+        Ok(())
+    })
+}
+
+#[test]
+fn invalid_filter() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+        user(1, "jonah").save(&db).await?;
+
+        // maps with non-string keys can not be serialized into a filter
+        let names = std::collections::HashMap::from([((1, 2), "jonah")]);
+        let filter = filter!(User, age > 18).or(Filter::eq("name", names));
+        assert!(matches!(
+            User::find_one(&db, filter, None).await,
+            Err(MustyError::InvalidFilter(_))
+        ));
+        Ok(())
+    })
+}
+
+#[test]
+fn filter_macro_uses_stored_field_names() {
+    assert_eq!(filter!(User, age == 20), Filter::eq("years", 20));
+}
+
+#[test]
+fn find_many() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        for (id, name) in [(1, "jonah"), (2, "alex"), (3, "bob"), (4, "alice")] {
+            user(id, name).save(&db).await?;
+        }
+
+        let names = |users: Vec<User>| users.into_iter().map(|user| user.name).collect::<Vec<_>>();
+
+        let users: Vec<User> = User::find_many(&db, filter!(User, name != "bob"), None)
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(names(users), ["jonah", "alex", "alice"]);
+
+        let options = QueryOptions::new().skip(1).limit(2);
+        let users: Vec<User> = User::find_many(&db, MemoryFilter::all(), options)
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(names(users), ["alex", "bob"]);
+        Ok(())
+    })
+}
+
+#[test]
+fn query_options() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        for (id, name, age) in [
+            (1, "jonah", 30),
+            (2, "alex", 20),
+            (3, "bob", 30),
+            (4, "alice", 25),
+        ] {
+            let mut user = user(id, name);
+            user.age = age;
+            user.save(&db).await?;
+        }
+
+        let options = QueryOptions::new()
+            .sort(User::fields().age.desc())
+            .sort(User::fields().name.asc());
+        let users: Vec<User> = User::find_many(&db, MemoryFilter::all(), options.clone())
+            .await?
+            .try_collect()
+            .await?;
+        let names: Vec<_> = users.into_iter().map(|user| user.name).collect();
+        assert_eq!(names, ["bob", "jonah", "alice", "alex"]);
+
+        let youngest = User::find_one(
+            &db,
+            MemoryFilter::all(),
+            QueryOptions::new().sort(User::fields().age.asc()),
+        )
+        .await?;
+        assert_eq!(youngest.map(|user| user.name), Some(String::from("alex")));
+
+        let second = User::find_one(&db, MemoryFilter::all(), options.skip(1)).await?;
+        assert_eq!(second.map(|user| user.name), Some(String::from("jonah")));
+        Ok(())
+    })
+}
+
+#[test]
+fn projection() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        let mut jonah = user(1, "jonah");
+        jonah.email = Some(String::from("jonah@example.com"));
+        jonah.save(&db).await?;
+
+        let options = QueryOptions::new()
+            .project(User::fields().name)
+            .project(User::fields().age);
+        let found = User::find_one(&db, MemoryFilter::all(), options)
+            .await?
+            .expect("user should exist");
+        assert_eq!(found.id, 1);
+        assert_eq!(found.name, "jonah");
+        assert_eq!(found.email, None);
+        Ok(())
+    })
+}
+
+#[test]
+fn save_requires_id() {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        let mut anonymous = user(0, "anonymous");
+        anonymous.id = Id::default();
+        assert!(matches!(
+            anonymous.save(&db).await,
+            Err(MustyError::ModelIdRequiredForOperation)
+        ));
+    })
+}
+
+#[model]
+struct Page {
+    id: u32,
+    title: String,
+    #[musty(version)]
+    revision: u64,
+}
+
+#[test]
+fn version_conflict() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        let mut page = Page {
+            id: 1.into(),
+            title: "draft".to_string(),
+            revision: 0,
+        };
+        page.save(&db).await?;
+        assert_eq!(page.revision, 1);
+
+        let mut first = Page::get_by_id(&db, 1).await?.expect("page should exist");
+        let mut second = Page::get_by_id(&db, 1).await?.expect("page should exist");
+
+        first.title = "first".to_string();
+        assert_eq!(first.save(&db).await?, SaveOutcome::Updated);
+        assert_eq!(first.revision, 2);
+
+        second.title = "second".to_string();
+        assert!(matches!(
+            second.save(&db).await,
+            Err(MustyError::VersionConflict)
+        ));
+        assert_eq!(second.revision, 1);
+
+        let stored = Page::get_by_id(&db, 1).await?.expect("page should exist");
+        assert_eq!((stored.title.as_str(), stored.revision), ("first", 2));
+
+        // the version can not be stored once it is out of range of i64
+        let mut page = Page {
+            id: 2.into(),
+            title: "draft".to_string(),
+            revision: u64::MAX,
+        };
+        assert!(matches!(
+            page.save(&db).await,
+            Err(MustyError::VersionOutOfRange)
+        ));
+        assert_eq!(page.revision, u64::MAX);
+        Ok(())
+    })
+}
+
+#[model]
+struct Note {
+    id: u32,
+    text: String,
+    #[musty(created_at)]
+    created_at: Option<SystemTime>,
+    #[musty(updated_at)]
+    updated_at: SystemTime,
+}
+
+#[test]
+fn timestamps() -> musty::Result<()> {
+    block_on(async {
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let updated = created + Duration::from_secs(60);
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        let mut note = Note {
+            id: 1.into(),
+            text: "hello".to_string(),
+            created_at: None,
+            updated_at: SystemTime::UNIX_EPOCH,
+        };
+        note.save(&db.clone().with_clock(FixedClock(created)))
+            .await?;
+        assert_eq!(note.created_at, Some(created));
+        assert_eq!(note.updated_at, created);
+
+        note.text = "world".to_string();
+        note.save(&db.clone().with_clock(FixedClock(updated)))
+            .await?;
+        assert_eq!(note.created_at, Some(created));
+        assert_eq!(note.updated_at, updated);
+
+        let stored = Note::get_by_id(&db, 1).await?.expect("note should exist");
+        assert_eq!(stored.created_at, Some(created));
+        assert_eq!(stored.updated_at, updated);
+
+        // a model replacing a stored one is not inserted, so its creation time is left as is
+        let mut replacement = Note {
+            id: 1.into(),
+            text: "replaced".to_string(),
+            created_at: None,
+            updated_at: SystemTime::UNIX_EPOCH,
+        };
+        replacement
+            .save(&db.clone().with_clock(FixedClock(updated)))
+            .await?;
+        assert_eq!(replacement.created_at, None);
+        assert_eq!(replacement.updated_at, updated);
+        Ok(())
+    })
+}
+
+#[model(soft_delete)]
+struct Invoice {
+    id: u32,
+    total: u32,
+    deleted_at: Option<SystemTime>,
+}
+
+#[test]
+fn soft_delete() -> musty::Result<()> {
+    block_on(async {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let db = Musty::from(MemoryBackend::new()).with_clock(FixedClock(now));
+
+        for (id, total) in [(1, 10), (2, 20)] {
+            Invoice {
+                id: id.into(),
+                total,
+                deleted_at: None,
+            }
+            .save(&db)
+            .await?;
+        }
+
+        let mut invoice = Invoice::get_by_id(&db, 1)
+            .await?
+            .expect("invoice should exist");
+        assert!(invoice.delete(&db).await?);
+        assert!(!invoice.delete(&db).await?);
+        assert_eq!(invoice.deleted_at, Some(now));
+
+        assert!(Invoice::get_by_id(&db, 1).await?.is_none());
+        assert!(Invoice::get_by_id_with_deleted(&db, 1).await?.is_some());
+        assert!(Invoice::find_one(&db, filter!(Invoice, total == 10), None)
+            .await?
+            .is_none());
+
+        let totals = |options: QueryOptions| async {
+            Invoice::find_many(&db, MemoryFilter::all(), options)
+                .await?
+                .map_ok(|invoice| invoice.total)
+                .try_collect::<Vec<_>>()
+                .await
+        };
+        assert_eq!(totals(QueryOptions::new()).await?, vec![20]);
+        assert_eq!(
+            totals(QueryOptions::new().with_deleted()).await?,
+            vec![10, 20]
+        );
+        assert_eq!(totals(QueryOptions::new().only_deleted()).await?, vec![10]);
+
+        assert!(invoice.restore(&db).await?);
+        assert!(Invoice::get_by_id(&db, 1).await?.is_some());
+
+        // only the marker is stored, unsaved changes are left out
+        invoice.total = 15;
+        assert!(invoice.delete(&db).await?);
+        let stored = Invoice::get_by_id_with_deleted(&db, 1)
+            .await?
+            .expect("invoice should exist");
+        assert_eq!((stored.total, stored.deleted_at), (10, Some(now)));
+
+        // a model which is not stored is not inserted by a soft delete
+        assert!(invoice.force_delete(&db).await?);
+        assert!(!invoice.restore(&db).await?);
+        assert_eq!(invoice.deleted_at, Some(now));
+        invoice.deleted_at = None;
+        assert!(!invoice.delete(&db).await?);
+        assert_eq!(invoice.deleted_at, None);
+        assert!(Invoice::get_by_id_with_deleted(&db, 1).await?.is_none());
+        Ok(())
+    })
+}
+
+static LOADED: AtomicUsize = AtomicUsize::new(0);
+
+#[model(hooks)]
+struct Member {
+    id: u32,
+    email: String,
+    #[musty(skip)]
+    loaded: bool,
+}
+
+#[async_trait]
+impl ModelHooks for Member {
+    async fn before_save<B: Backend>(&mut self, _db: &Musty<B>) -> musty::Result<()> {
+        self.email = self.email.trim().to_lowercase();
+        Ok(())
+    }
+
+    async fn before_delete<B: Backend>(&mut self, _db: &Musty<B>) -> musty::Result<()> {
+        if self.email.ends_with("@admin.com") {
+            return Err(anyhow::anyhow!("admins can not be deleted").into());
+        }
+        Ok(())
+    }
+
+    async fn after_load<B: Backend>(&mut self, _db: &Musty<B>) -> musty::Result<()> {
+        self.loaded = true;
+        LOADED.fetch_add(1, AtomicOrdering::SeqCst);
+        Ok(())
+    }
+}
+
+#[test]
+fn hooks() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        for (id, email) in [(1, " Jonah@Example.com "), (2, "alex@admin.com")] {
+            let mut member = Member {
+                id: id.into(),
+                email: email.to_string(),
+                loaded: false,
+            };
+            member.save(&db).await?;
+            assert!(!member.loaded);
+        }
+
+        let mut jonah = Member::get_by_id(&db, 1)
+            .await?
+            .expect("member should exist");
+        assert!(jonah.loaded);
+        assert_eq!(jonah.email, "jonah@example.com");
+
+        let members = Member::find_many(&db, MemoryFilter::all(), None)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert!(members.iter().all(|member| member.loaded));
+        assert_eq!(LOADED.load(AtomicOrdering::SeqCst), 3);
+
+        let mut alex = Member::get_by_id(&db, 2)
+            .await?
+            .expect("member should exist");
+        assert!(alex.delete(&db).await.is_err());
+        assert!(Member::get_by_id(&db, 2).await?.is_some());
+        assert!(jonah.delete(&db).await?);
+        Ok(())
+    })
+}
+
+#[model]
+struct Account {
+    id: u32,
+    #[musty(validate(length(min = 1, max = 16), regex = "^[a-z0-9_]+$"))]
+    username: String,
+    #[musty(rename = "mail", validate(email))]
+    email: Option<String>,
+    #[musty(validate(range(min = 13, max = 130), custom = "not_reserved"))]
+    age: u32,
+}
+
+fn not_reserved(age: &u32) -> Result<(), String> {
+    match age {
+        42 => Err("is reserved".to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[test]
+fn validation() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        let mut account = Account {
+            id: 1.into(),
+            username: "Not Valid".to_string(),
+            email: Some("jonah".to_string()),
+            age: 42,
+        };
+        let errors = match account.save(&db).await {
+            Err(MustyError::Validation(errors)) => errors,
+            other => panic!("expected a validation error, got {:?}", other),
+        };
+        let fields = errors
+            .errors()
+            .iter()
+            .map(|error| error.field.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec!["username", "mail", "age"]);
+        assert!(Account::get_by_id(&db, 1).await?.is_none());
+
+        account.username = "jonah".to_string();
+        account.email = None;
+        account.age = 20;
+        assert!(account.save(&db).await?.is_inserted());
+        Ok(())
+    })
+}
+
+#[model]
+struct Book {
+    id: u32,
+    title: String,
+    author: Ref<User>,
+}
+
+#[test]
+fn references() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        user(1, "jonah").save(&db).await?;
+        user(2, "alex").save(&db).await?;
+        for (id, author) in [(1, 1), (2, 2), (3, 1), (4, 3)] {
+            Book {
+                id: id.into(),
+                title: format!("book {}", id),
+                author: Ref::new(author),
+            }
+            .save(&db)
+            .await?;
+        }
+
+        let mut book = Book::get_by_id(&db, 1).await?.expect("book should exist");
+        assert!(!book.author.is_loaded());
+        let author = book.author.fetch(&db).await?.expect("author should exist");
+        assert_eq!(author.name, "jonah");
+        assert!(book.author.is_loaded());
+
+        let books = Book::find_many(&db, filter!(Book, author == 1), None).await?;
+        assert_eq!(books.try_collect::<Vec<_>>().await?.len(), 2);
+
+        let mut books: Vec<Book> = Book::find_many(&db, MemoryFilter::all(), None)
+            .await?
+            .try_collect()
+            .await?;
+        populate(&db, &mut books, |book| &mut book.author).await?;
+        let authors = books
+            .iter()
+            .map(|book| book.author.get().map(|author| author.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            authors,
+            vec![Some("jonah"), Some("alex"), Some("jonah"), None]
+        );
+        Ok(())
+    })
+}
+
+#[model]
+enum Activity {
+    Login {
+        id: u32,
+        user: String,
+    },
+    #[musty(rename = "purchase", model = "Purchase")]
+    Purchase {
+        id: u32,
+        user: String,
+        amount: u32,
+    },
+}
+
+#[test]
+fn enum_models() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        LoginActivity {
+            id: 1.into(),
+            user: "jonah".to_string(),
+        }
+        .save(&db)
+        .await?;
+        Activity::Purchase {
+            id: 2.into(),
+            user: "jonah".to_string(),
+            amount: 20,
+        }
+        .save(&db)
+        .await?;
+        Activity::Login {
+            id: 3.into(),
+            user: "alex".to_string(),
+        }
+        .save(&db)
+        .await?;
+
+        let activities: Vec<Activity> =
+            Activity::find_many(&db, filter!(Activity, user == "jonah"), None)
+                .await?
+                .try_collect()
+                .await?;
+        assert!(matches!(
+            activities.as_slice(),
+            [
+                Activity::Login { .. },
+                Activity::Purchase { amount: 20, .. }
+            ]
+        ));
+
+        let logins: Vec<LoginActivity> = LoginActivity::find_many(&db, MemoryFilter::all(), None)
+            .await?
+            .try_collect()
+            .await?;
+        let users = logins
+            .iter()
+            .map(|login| login.user.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(users, vec!["jonah", "alex"]);
+
+        assert!(Purchase::get_by_id(&db, 1).await?.is_none());
+        let purchase = Purchase::get_by_id(&db, 2)
+            .await?
+            .expect("purchase should exist");
+        assert_eq!(purchase.amount, 20);
+
+        let activity = Activity::from(purchase);
+        assert_eq!(activity.id(), &2);
+        assert!(LoginActivity::try_from(activity).is_err());
+        Ok(())
+    })
+}
+
+#[test]
+fn enum_model_variants_with_the_same_id() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        let mut login = LoginActivity {
+            id: 1.into(),
+            user: "jonah".to_string(),
+        };
+        login.save(&db).await?;
+
+        let mut purchase = Purchase {
+            id: 1.into(),
+            user: "alex".to_string(),
+            amount: 20,
+        };
+        assert!(matches!(
+            purchase.save(&db).await,
+            Err(MustyError::IdConflict)
+        ));
+        assert!(!purchase.delete(&db).await?);
+
+        let login = LoginActivity::get_by_id(&db, 1)
+            .await?
+            .expect("login should not be replaced or deleted");
+        assert_eq!(login.user, "jonah");
+        assert!(Purchase::get_by_id(&db, 1).await?.is_none());
+        Ok(())
+    })
+}
+
+#[test]
+fn count_and_exists() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        for (id, user) in [(1, "jonah"), (2, "jonah"), (3, "alex")] {
+            LoginActivity {
+                id: id.into(),
+                user: user.to_string(),
+            }
+            .save(&db)
+            .await?;
+        }
+        Purchase {
+            id: 4.into(),
+            user: "jonah".to_string(),
+            amount: 20,
+        }
+        .save(&db)
+        .await?;
+
+        assert_eq!(Activity::count(&db, MemoryFilter::all()).await?, 4);
+        assert_eq!(LoginActivity::count(&db, MemoryFilter::all()).await?, 3);
+        assert_eq!(
+            LoginActivity::count(&db, filter!(LoginActivity, user == "jonah")).await?,
+            2
+        );
+        assert!(Purchase::exists(&db, filter!(Purchase, user == "jonah")).await?);
+        assert!(!Purchase::exists(&db, filter!(Purchase, user == "alex")).await?);
+
+        let mut invoice = Invoice {
+            id: 1.into(),
+            total: 10,
+            deleted_at: None,
+        };
+        invoice.save(&db).await?;
+        assert!(Invoice::exists(&db, MemoryFilter::all()).await?);
+        invoice.delete(&db).await?;
+        assert_eq!(Invoice::count(&db, MemoryFilter::all()).await?, 0);
+        assert!(!Invoice::exists(&db, MemoryFilter::all()).await?);
+        Ok(())
+    })
+}
+
+/// An event with a typed payload
+#[model]
+#[derive(Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct Event<P>
+where
+    P: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + Unpin + 'static,
+{
+    id: u32,
+    event_name: String,
+    #[serde(rename = "data")]
+    payload: P,
+    #[allow(dead_code)]
+    #[serde(default)]
+    retries: u32,
+}
+
+#[test]
+fn generic_models_keep_derives_and_serde_attributes() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+
+        assert_eq!(Event::<Vec<u32>>::fields().event_name.as_ref(), "eventName");
+        assert_eq!(Event::<Vec<u32>>::fields().payload.as_ref(), "data");
+
+        let event = Event {
+            id: 1.into(),
+            event_name: "signup".to_string(),
+            payload: vec![1, 2],
+            retries: 0,
+        };
+        event.clone().save(&db).await?;
+
+        let found = Event::<Vec<u32>>::find_one(&db, json!({ "eventName": "signup" }), None)
+            .await?
+            .expect("event should exist");
+        assert!(found == event);
+
+        let found =
+            Event::<Vec<u32>>::find_one(&db, filter!(Event<Vec<u32>>, payload == vec![1, 2]), None)
+                .await?;
+        assert!(found.is_some());
+        Ok(())
+    })
+}
+
+#[model(discriminator = "kind")]
+#[derive(Clone)]
+#[serde(rename_all = "snake_case")]
+enum Shipment {
+    #[serde(rename_all = "camelCase")]
+    ByAir {
+        id: u32,
+        flight_number: String,
+    },
+    ByRoad {
+        id: u32,
+        plate: String,
+    },
+}
+
+#[test]
+fn enum_models_keep_derives_and_serde_attributes() -> musty::Result<()> {
+    let shipment = Shipment::ByAir {
+        id: 1.into(),
+        flight_number: "AF123".to_string(),
+    };
+    assert_eq!(
+        serde_json::to_value(shipment.clone())?,
+        json!({ "kind": "by_air", "flightNumber": "AF123" })
+    );
+
+    let by_air = ByAirShipment::try_from(shipment).expect("shipment should be by air");
+    assert_eq!(
+        serde_json::to_value(by_air.clone())?,
+        json!({ "kind": "by_air", "flightNumber": "AF123" })
+    );
+    assert_eq!(
+        ByAirShipment::fields().flight_number.as_ref(),
+        "flightNumber"
+    );
+    assert_eq!(ByRoadShipment::fields().plate.as_ref(), "plate");
+    Ok(())
+}
+
+#[model]
+struct Order {
+    #[musty(id(generate = "uuid_v7"))]
+    id: musty::generate::Uuid,
+    total: u32,
+}
+
+#[model]
+struct Ticket {
+    #[musty(id(generate = "sequence"))]
+    id: i64,
+    title: String,
+}
+
+#[model]
+struct Upload {
+    #[musty(id(generate = "ulid"))]
+    id: String,
+}
+
+#[model]
+struct Message {
+    #[musty(id(generate = "snowflake"))]
+    id: i64,
+}
+
+#[test]
+fn generated_ids() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = Musty::from(MemoryBackend::new()).with_snowflake_node(7);
+
+        let mut order = Order {
+            id: Id::default(),
+            total: 20,
+        };
+        let outcome = order.save(&db).await?;
+        assert!(!order.id.is_none());
+        assert_eq!(
+            outcome,
+            SaveOutcome::Inserted {
+                id: order.id.clone()
+            }
+        );
+        assert!(Order::get_by_id(&db, order.id.clone()).await?.is_some());
+
+        // models with an id keep it
+        let id = musty::generate::Uuid::new_v4();
+        let mut order = Order {
+            id: id.into(),
+            total: 30,
+        };
+        order.save(&db).await?;
+        assert_eq!(order.id, id);
+        let found = Order::find_one(&db, Filter::eq("_id", id), None).await?;
+        assert_eq!(found.map(|order| order.total), Some(30));
+
+        for expected in 1..=3 {
+            let mut ticket = Ticket {
+                id: Id::default(),
+                title: "bug".to_string(),
+            };
+            ticket.save(&db).await?;
+            assert_eq!(ticket.id, expected);
+        }
+
+        let mut first = Upload { id: Id::default() };
+        let mut second = Upload { id: Id::default() };
+        first.save(&db).await?;
+        second.save(&db).await?;
+        assert_ne!(first.id, second.id);
+
+        let mut first = Message { id: Id::default() };
+        let mut second = Message { id: Id::default() };
+        first.save(&db).await?;
+        second.save(&db).await?;
+        let snowflake = |message: &Message| serde_json::to_value(&message.id).unwrap().as_i64();
+        assert!(snowflake(&second) > snowflake(&first));
+        assert_eq!(snowflake(&first).map(|id| (id >> 12) & 1023), Some(7));
+        Ok(())
+    })
+}
+
+#[test]
+fn large_integers() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+        // snowflake-sized ids which are the same as `f64`s
+        let first = 1_i64 << 60;
+        let second = first + 1;
+        for id in [second, first] {
+            Message { id: id.into() }.save(&db).await?;
+        }
+        let ids = |messages: Vec<Message>| {
+            messages
+                .into_iter()
+                .map(|message| serde_json::to_value(&message.id).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let found: Vec<Message> = Message::find_many(&db, Filter::eq("_id", second), None)
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(ids(found), vec![json!(second)]);
+
+        let found: Vec<Message> = Message::find_many(&db, Filter::gt("_id", first), None)
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(ids(found), vec![json!(second)]);
+
+        let options = QueryOptions::new().sort(Sort::desc("_id"));
+        let found: Vec<Message> = Message::find_many(&db, MemoryFilter::all(), options)
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(ids(found), vec![json!(second), json!(first)]);
+        Ok(())
+    })
+}
+
+#[model]
+#[derive(Clone)]
+struct Author {
+    id: u32,
+    name: String,
+}
+
+#[test]
+fn model_loader() -> musty::Result<()> {
+    block_on(async {
+        let db: Musty<MemoryBackend> = MemoryBackend::new().into();
+        for (id, name) in [(1, "jonah"), (2, "alex")] {
+            Author {
+                id: id.into(),
+                name: name.to_string(),
+            }
+            .save(&db)
+            .await?;
+        }
+
+        let loader = ModelLoader::<Author, _>::new(&db);
+        let (first, second, missing) =
+            futures::try_join!(loader.load(1), loader.load(2), loader.load(3))?;
+        assert_eq!(first.map(|author| author.name).as_deref(), Some("jonah"));
+        assert_eq!(second.map(|author| author.name).as_deref(), Some("alex"));
+        assert!(missing.is_none());
+
+        let names = |authors: Vec<Author>| {
+            authors
+                .into_iter()
+                .map(|author| author.name)
+                .collect::<Vec<_>>()
+        };
+        let ids = [2, 3, 1].map(Id::from);
+        assert_eq!(
+            names(loader.load_many(ids.clone()).await?),
+            vec!["alex", "jonah"]
+        );
+
+        // loaded models are cached until the cache is cleared
+        Author {
+            id: 1.into(),
+            name: "sam".to_string(),
+        }
+        .save(&db)
+        .await?;
+        let cached = loader.load(1).await?.expect("author should exist");
+        assert_eq!(cached.name, "jonah");
+        loader.clear();
+        let loaded = loader.load(1).await?.expect("author should exist");
+        assert_eq!(loaded.name, "sam");
+
+        loader.prime(Author {
+            id: 3.into(),
+            name: "kim".to_string(),
+        });
+        assert_eq!(
+            names(loader.load_many(ids).await?),
+            vec!["alex", "kim", "sam"]
+        );
+        Ok(())
+    })
+}
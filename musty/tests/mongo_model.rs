@@ -0,0 +1,301 @@
+#![cfg(feature = "mongodb")]
+
+use std::{
+    collections::HashMap,
+    time::{Duration, SystemTime},
+};
+
+use bson::{doc, oid::ObjectId, DateTime, Document};
+use mongodb::{
+    options::{
+        Acknowledgment, ClientOptions, ReadConcern, ReadPreference, SelectionCriteria,
+        ServerAddress, WriteConcern,
+    },
+    Client,
+};
+use musty::prelude::*;
+
+#[model(mongo(
+    collection = "accounts",
+    read_concern = "majority",
+    write_concern = "majority",
+    journal = true,
+    read_preference = "secondaryPreferred"
+))]
+struct Account {
+    id: ObjectId,
+    name: String,
+}
+
+#[model(mongo())]
+struct Session {
+    id: ObjectId,
+}
+
+#[model(mongo(index(keys = "author, -created_at", unique, name = "author_created_at")))]
+struct Post {
+    id: ObjectId,
+    #[musty(mongo(index))]
+    author: String,
+    #[musty(mongo(text))]
+    title: String,
+    #[musty(rename = "body", mongo(text))]
+    content: String,
+    #[musty(mongo(unique, sparse))]
+    slug: Option<String>,
+    #[musty(mongo(ttl = "30d"))]
+    created_at: DateTime,
+}
+
+#[model(mongo(), soft_delete)]
+struct Event {
+    id: ObjectId,
+    #[musty(created_at)]
+    created_at: DateTime,
+    #[musty(updated_at)]
+    updated_at: Option<DateTime>,
+    deleted_at: Option<DateTime>,
+}
+
+#[model(mongo(collection = "notifications"), discriminator = "kind")]
+enum Notification {
+    Email {
+        id: ObjectId,
+        #[musty(mongo(index))]
+        recipient: String,
+        subject: String,
+    },
+    Push {
+        id: ObjectId,
+        #[musty(mongo(index))]
+        recipient: String,
+        device: String,
+    },
+}
+
+#[model(mongo())]
+struct Order {
+    #[musty(id(generate = "uuid_v4"))]
+    id: musty::generate::Uuid,
+    total: u32,
+}
+
+#[test]
+fn concerns_from_attributes() {
+    assert_eq!(Account::COLLECTION_NAME, "accounts");
+    assert_eq!(Account::read_concern(), Some(ReadConcern::majority()));
+    assert_eq!(
+        Account::write_concern(),
+        Some(
+            WriteConcern::builder()
+                .w(Acknowledgment::Majority)
+                .journal(true)
+                .build()
+        )
+    );
+    assert!(matches!(
+        Account::selection_criteria(),
+        Some(SelectionCriteria::ReadPreference(
+            ReadPreference::SecondaryPreferred { .. }
+        ))
+    ));
+
+    let options = Account::collection_options();
+    assert_eq!(options.read_concern, Account::read_concern());
+    assert_eq!(options.write_concern, Account::write_concern());
+}
+
+#[test]
+fn default_concerns() {
+    assert_eq!(Session::COLLECTION_NAME, "sessions");
+    assert_eq!(Session::read_concern(), None);
+    assert_eq!(Session::write_concern(), None);
+    assert!(Session::selection_criteria().is_none());
+}
+
+#[test]
+fn indexes_from_attributes() {
+    let indexes = Post::indexes();
+    let keys = indexes
+        .iter()
+        .map(|index| index.keys.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        vec![
+            doc! { "author": 1 },
+            doc! { "slug": 1 },
+            doc! { "created_at": 1 },
+            doc! { "title": "text", "body": "text" },
+            doc! { "author": 1, "created_at": -1 },
+        ]
+    );
+
+    let options = indexes
+        .iter()
+        .map(|index| index.options.clone().unwrap_or_default())
+        .collect::<Vec<_>>();
+    assert_eq!(options[0].unique, None);
+    assert_eq!(
+        (options[1].unique, options[1].sparse),
+        (Some(true), Some(true))
+    );
+    assert_eq!(
+        options[2].expire_after,
+        Some(Duration::from_secs(30 * 24 * 60 * 60))
+    );
+    assert_eq!(options[4].unique, Some(true));
+    assert_eq!(options[4].name.as_deref(), Some("author_created_at"));
+
+    assert!(Session::indexes().is_empty());
+}
+
+#[test]
+fn timestamps_as_bson_datetimes() {
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+    let mut event = Event {
+        id: ObjectId::new().into(),
+        created_at: DateTime::from_millis(0),
+        updated_at: None,
+        deleted_at: None,
+    };
+    event.set_timestamps(now, true);
+    assert_eq!(event.created_at, DateTime::from_system_time(now));
+    assert_eq!(event.updated_at, Some(DateTime::from_system_time(now)));
+
+    let later = now + Duration::from_secs(60);
+    event.set_timestamps(later, false);
+    assert_eq!(event.created_at, DateTime::from_system_time(now));
+    assert_eq!(event.updated_at, Some(DateTime::from_system_time(later)));
+
+    event.set_deleted_at(Some(later));
+    assert_eq!(event.deleted_at, Some(DateTime::from_system_time(later)));
+}
+
+#[test]
+fn enum_models_share_a_collection() -> musty::Result<()> {
+    assert_eq!(Notification::COLLECTION_NAME, "notifications");
+    assert_eq!(EmailNotification::COLLECTION_NAME, "notifications");
+    assert_eq!(PushNotification::DISCRIMINATOR, Some(("kind", "Push")));
+    assert_eq!(Notification::DISCRIMINATOR, None);
+    assert_eq!(Notification::fields().recipient.path(), "recipient");
+
+    let keys = Notification::indexes()
+        .into_iter()
+        .map(|index| index.keys)
+        .collect::<Vec<_>>();
+    assert_eq!(keys, vec![doc! { "recipient": 1 }]);
+    assert!(EmailNotification::indexes().is_empty());
+
+    let id = ObjectId::new();
+    let email = EmailNotification {
+        id: id.into(),
+        recipient: "jonah@example.com".to_string(),
+        subject: "Hello".to_string(),
+    };
+    let document = email.document_from_model()?;
+    assert_eq!(
+        document,
+        doc! { "kind": "Email", "_id": id, "recipient": "jonah@example.com", "subject": "Hello" }
+    );
+    assert!(matches!(
+        Notification::model_from_document(document)?,
+        Notification::Email { subject, .. } if subject == "Hello"
+    ));
+    Ok(())
+}
+
+#[test]
+fn aggregation_pipelines() -> musty::Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        // the client never connects, pipelines are only built
+        let options = ClientOptions::builder()
+            .hosts(vec![ServerAddress::Tcp {
+                host: "localhost".to_string(),
+                port: None,
+            }])
+            .build();
+        let db = Musty::from(Client::with_options(options)?.database("musty"));
+
+        // the distinct values of a field have the type of the field (the query is never sent)
+        let authors = Post::distinct(&db, Post::fields().author, None, None);
+        let _: &dyn std::future::Future<Output = musty::Result<Vec<String>>> = &authors;
+
+        let pipeline = Post::aggregate(&db)
+            .match_(Post::fields().title.eq("Hello"))
+            .lookup::<Account>(Post::fields().author, Account::fields().name, "accounts")
+            .unwind("accounts")
+            .group(Post::fields().author, doc! { "posts": { "$sum": 1 } })
+            .sort(Sort::desc("posts"))
+            .sort(Sort::asc("_id"))
+            .project(["posts"])
+            .limit(10)
+            .pipeline()?;
+        assert_eq!(
+            pipeline,
+            vec![
+                doc! { "$match": { "title": "Hello" } },
+                doc! { "$lookup": { "from": "accounts", "localField": "author", "foreignField": "name", "as": "accounts" } },
+                doc! { "$unwind": "$accounts" },
+                doc! { "$group": { "_id": "$author", "posts": { "$sum": 1 } } },
+                doc! { "$sort": { "posts": -1, "_id": 1 } },
+                doc! { "$project": { "posts": 1 } },
+                doc! { "$limit": 10_i64 },
+            ]
+        );
+
+        let pipeline = EmailNotification::aggregate(&db)
+            .facet("recipients", |notifications| {
+                notifications.group(Notification::fields().recipient, doc! {})
+            })
+            .facet("total", |notifications| {
+                notifications.stage(doc! { "$count": "total" })
+            })
+            .pipeline()?;
+        assert_eq!(
+            pipeline,
+            vec![
+                doc! { "$match": { "kind": "Email" } },
+                doc! {
+                    "$facet": {
+                        "recipients": [{ "$group": { "_id": "$recipient" } }],
+                        "total": [{ "$count": "total" }],
+                    }
+                },
+            ]
+        );
+
+        // maps with non-string keys can not be serialized into a filter
+        let invalid = HashMap::from([((1, 2), "Hello")]);
+        assert!(matches!(
+            Post::aggregate(&db)
+                .match_(Post::fields().title.eq("Hello"))
+                .facet("titles", |posts| posts.match_(Filter::eq("title", invalid)))
+                .pipeline(),
+            Err(MustyError::InvalidFilter(_))
+        ));
+        Ok(())
+    })
+}
+
+#[test]
+fn uuids_are_filtered_as_binary() -> musty::Result<()> {
+    let id = musty::generate::Uuid::new_v4();
+    let binary = bson::Bson::from(bson::Uuid::from(uuid::Uuid::from(id)));
+    assert!(matches!(
+        &binary,
+        bson::Bson::Binary(binary) if binary.subtype == bson::spec::BinarySubtype::Uuid
+    ));
+
+    let filter = Document::try_from(filter!(Order, id == id))?;
+    assert_eq!(filter, doc! { "_id": binary.clone() });
+    let filter = Document::try_from(Order::fields().id.in_([id]))?;
+    assert_eq!(filter, doc! { "_id": { "$in": [binary.clone()] } });
+    let update = Document::try_from(Order::fields().id.set(id))?;
+    assert_eq!(update, doc! { "$set": { "_id": binary } });
+    Ok(())
+}